@@ -1,56 +1,74 @@
 use std::collections::HashSet;
 
 pub use crate::prelude::*;
+use serde::{Deserialize, Serialize};
 
-#[derive(Clone, Copy, Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
 pub struct Render {
     pub color: ColorPair,
     pub glyph: FontCharType,
 }
 
-#[derive(Clone, Copy, Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
 pub struct Player {
     pub map_level: u32,
 }
 
-#[derive(Clone, Copy, Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
 pub struct Enemy;
 
-#[derive(Clone, Copy, Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
 pub struct MovingRandomly;
 
-#[derive(Clone, Copy, Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
 pub struct ChasingPlayer;
 
-#[derive(Clone, Copy, Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
 pub struct WantsToMove {
     pub entity: Entity,
     pub from: Point,
     pub destination: Point,
 }
 
-#[derive(Clone, Copy, Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
 pub struct WantsToAttack {
     pub attacker: Entity,
     pub victim: Entity,
 }
 
-#[derive(Clone, Copy, Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
 pub struct Health {
     pub current: i32,
     pub max: i32,
 }
 
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct Name(pub String);
 
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct Item;
 
-#[derive(Clone, Debug, PartialEq)]
+/// Marks the artifact of the victory condition, the Amulet of Yala by default.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct AmuletOfYala;
 
-#[derive(Clone, Debug, PartialEq)]
+/// Marks the monster that must be slain to win.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Boss;
+
+/// What killed the player.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum SlainBy {
+    /// A monster, by its name.
+    Monster(String),
+    /// A trap, by its name.
+    Trap(String),
+    /// The poison of a trap, by the name of the trap.
+    Poison(String),
+    Lava,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct FieldOfView {
     pub visible_tiles: HashSet<Point>,
     pub radius: i32,
@@ -75,25 +93,99 @@ impl FieldOfView {
     }
 }
 
-#[derive(Clone, Copy, Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
 pub struct ProvidesHealing {
     pub amount: i32,
 }
 
-#[derive(Clone, Copy, Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
 pub struct ProvidesDungeonMap;
 
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct Carried(pub Entity);
 
-#[derive(Clone, Copy, Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
 pub struct ActivateItem {
     pub used_by: Entity,
     pub item: Entity,
 }
 
-#[derive(Clone, Copy, Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
 pub struct Damage(pub i32);
 
-#[derive(Clone, Copy, Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
 pub struct Weapon;
+
+/// Digs through walls in `turns` turns, when a monster has it or when it is carried.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Digger {
+    pub turns: i32,
+}
+
+/// How far the entity has dug into the wall at `target`.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Digging {
+    pub target: Point,
+    pub progress: i32,
+}
+
+/// Blows up the walls within `radius` of whoever uses it.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Explodes {
+    pub radius: i32,
+}
+
+/// Lights the tiles around the entity, or around whoever carries it.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct LightSource {
+    pub radius: i32,
+    pub color: RGB,
+}
+
+/// A floor hazard, set off by whoever walks onto it.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Trap;
+
+/// A trap the player hasn't found yet: it is neither drawn nor avoided by the monsters.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Hidden;
+
+/// Sends whoever triggers the trap to a random spot of the level.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Teleports;
+
+/// Alerts every monster of the level to where the player is.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SoundsAlarm;
+
+/// Poisons whoever triggers the trap for `turns` turns.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Poisons {
+    pub turns: i32,
+}
+
+/// Loses 1 HP at the end of each of the next `turns` turns.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Poisoned {
+    pub turns: i32,
+    /// What the poison came from, in case it kills the player.
+    pub source: String,
+}
+
+/// A monster that hunts the player even when it can't see them.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Alerted;
+
+/// The energy a monster spends to act once.
+pub const ACTION_COST: i32 = 100;
+
+/// The speed of monsters without a `Speed`, which act once per turn of the player.
+pub const NORMAL_SPEED: i32 = ACTION_COST;
+
+/// How much energy a monster gains on each turn of the player.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Speed(pub i32);
+
+/// The energy a monster has accumulated, acting whenever it reaches `ACTION_COST`.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Energy(pub i32);
@@ -0,0 +1,69 @@
+use crate::prelude::*;
+use std::collections::BTreeMap;
+
+/// A level the player has left: its map, its theme and every entity that was on it.
+pub struct StoredLevel {
+    pub map: Map,
+    pub theme: ThemeKind,
+    pub world: World,
+}
+
+/// The levels of the dungeon other than the one being played, which lives in the main
+/// `World` and the `Map` resource, so the player can go back to them.
+#[derive(Default)]
+pub struct Dungeon {
+    pub levels: BTreeMap<u32, StoredLevel>,
+}
+
+impl Dungeon {
+    /// Moves everything on the current level except the player and what they carry out of
+    /// `ecs`, and stores it along with the level map and theme.
+    pub fn store(&mut self, map_level: u32, ecs: &mut World, map: Map, theme: ThemeKind) {
+        let mut world = World::default();
+        world.move_from(ecs, &(!component::<Player>() & !component::<Carried>()));
+        self.levels
+            .insert(map_level, StoredLevel { map, theme, world });
+    }
+
+    /// Takes a stored level out of the dungeon, moving its entities back into `ecs`.
+    pub fn restore(&mut self, map_level: u32, ecs: &mut World) -> Option<(Map, ThemeKind)> {
+        let mut level = self.levels.remove(&map_level)?;
+        ecs.move_from(&mut level.world, &any());
+        Some((level.map, level.theme))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stored_level_keeps_its_entities() {
+        let mut ecs = World::default();
+        spawn_player(&mut ecs, Point::new(1, 1), &GameConfig::default());
+        let player = *<Entity>::query()
+            .filter(component::<Player>())
+            .iter(&ecs)
+            .next()
+            .unwrap();
+        let potion = ecs.push((Item, Name("Potion".to_string()), Carried(player)));
+        let goblin = ecs.push((Enemy, Point::new(5, 5)));
+        let mut map = Map::default();
+        map.revealed_tiles[3] = true;
+
+        let mut dungeon = Dungeon::default();
+        dungeon.store(0, &mut ecs, map, ThemeKind::Forest);
+        assert!(ecs.entry(player).is_some());
+        assert!(ecs.entry(potion).is_some());
+        assert!(ecs.entry(goblin).is_none());
+
+        let (map, theme) = dungeon.restore(0, &mut ecs).unwrap();
+        assert!(map.revealed_tiles[3]);
+        assert_eq!(theme, ThemeKind::Forest);
+        assert_eq!(
+            ecs.entry(goblin).unwrap().get_component::<Point>(),
+            Ok(&Point::new(5, 5))
+        );
+        assert!(dungeon.restore(0, &mut ecs).is_none());
+    }
+}
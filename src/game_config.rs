@@ -0,0 +1,196 @@
+use crate::prelude::*;
+use ron::de::from_reader;
+use serde::{Deserialize, Serialize};
+use std::fmt::Debug;
+use std::fs::File;
+
+pub const GAME_CONFIG_FILE: &str = "resources/game_config.ron";
+
+/// The smallest map width and height the architects can lay a level out in.
+pub const MIN_MAP_SIZE: i32 = 20;
+
+/// The gameplay tuning values. Any field missing from the config file keeps its default.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GameConfig {
+    /// The size of the generated levels, which can be larger or smaller than the screen.
+    pub map_width: i32,
+    pub map_height: i32,
+    /// Monsters spawned by the architects that don't place them in rooms.
+    pub num_monsters: usize,
+    /// Rooms carved by the rooms architect.
+    pub num_rooms: usize,
+    /// Traps hidden on each level, on tiles of their own.
+    pub num_traps: usize,
+    /// How many steps a drunkard walks before giving up.
+    pub stagger_distance: usize,
+    /// The share of the map the drunkard walk architect digs out, between 0 and 1.
+    pub desired_floor: f32,
+    /// A cell of the initial cellular automata noise is a floor if a roll of 0..100 is above this.
+    pub automata_floor_threshold: i32,
+    pub player_hp: i32,
+    pub player_fov: i32,
+    pub enemy_fov: i32,
+    /// Whether the player and the monsters can also move diagonally.
+    pub diagonal_movement: bool,
+    /// How many levels the dungeon has. The deepest one has no exit.
+    pub dungeon_depth: u32,
+    pub victory: VictoryCondition,
+}
+
+impl Default for GameConfig {
+    fn default() -> Self {
+        Self {
+            map_width: 80,
+            map_height: 50,
+            num_monsters: 50,
+            num_rooms: 20,
+            num_traps: 6,
+            stagger_distance: 400,
+            desired_floor: 1.0 / 3.0,
+            automata_floor_threshold: 55,
+            player_hp: 10,
+            player_fov: 8,
+            enemy_fov: 6,
+            diagonal_movement: false,
+            dungeon_depth: 3,
+            victory: VictoryCondition::default(),
+        }
+    }
+}
+
+impl GameConfig {
+    /// Loads the config file, falling back to the defaults if it can't be read. Values out
+    /// of their range are brought back into it, but a victory that can't be achieved is an
+    /// error.
+    pub fn load() -> Result<Self, String> {
+        match File::open(GAME_CONFIG_FILE)
+            .map_err(ron::Error::from)
+            .and_then(from_reader::<_, Self>)
+        {
+            Ok(config) => {
+                let config = config.validated();
+                config
+                    .check_victory()
+                    .map_err(|e| format!("Invalid victory in {}: {}", GAME_CONFIG_FILE, e))?;
+                Ok(config)
+            }
+            Err(e) => {
+                println!("Warning: Unable to load {}: {}", GAME_CONFIG_FILE, e);
+                Ok(Self::default())
+            }
+        }
+    }
+
+    /// Checks that the victory can be achieved: its goal must be in the dungeon, and a boss
+    /// must be one of the enemy templates.
+    pub fn check_victory(&self) -> Result<(), String> {
+        if let Some(depth) = self.victory.goal_depth() {
+            if depth >= self.dungeon_depth {
+                return Err(format!(
+                    "the goal is on level {} but the dungeon only has {} levels",
+                    depth + 1,
+                    self.dungeon_depth
+                ));
+            }
+        }
+        if let VictoryCondition::SlayBoss { boss, .. } = &self.victory {
+            if !is_enemy_template(boss) {
+                return Err(format!(
+                    "the boss '{}' is not an enemy in resources/template.ron",
+                    boss
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Clamps every value to a range the game can play with, warning about the ones that
+    /// were out of it.
+    pub fn validated(mut self) -> Self {
+        clamp("map_width", &mut self.map_width, MIN_MAP_SIZE, 1000);
+        clamp("map_height", &mut self.map_height, MIN_MAP_SIZE, 1000);
+        clamp("num_monsters", &mut self.num_monsters, 0, 1000);
+        clamp("num_rooms", &mut self.num_rooms, 1, 100);
+        clamp("num_traps", &mut self.num_traps, 0, 1000);
+        clamp("stagger_distance", &mut self.stagger_distance, 10, 10_000);
+        // The drunkards never dig the edge of the map, so they can't dig all of it.
+        clamp("desired_floor", &mut self.desired_floor, 0.0, 0.6);
+        clamp(
+            "automata_floor_threshold",
+            &mut self.automata_floor_threshold,
+            0,
+            60,
+        );
+        clamp("player_hp", &mut self.player_hp, 1, 1000);
+        clamp("player_fov", &mut self.player_fov, 1, 50);
+        clamp("enemy_fov", &mut self.enemy_fov, 1, 50);
+        clamp("dungeon_depth", &mut self.dungeon_depth, 1, 100);
+        self
+    }
+}
+
+fn clamp<T: PartialOrd + Copy + Debug>(name: &str, value: &mut T, min: T, max: T) {
+    let clamped = if *value < min {
+        min
+    } else if *value > max {
+        max
+    } else {
+        return;
+    };
+    println!(
+        "Warning: {} in {} must be between {:?} and {:?}, using {:?}",
+        name, GAME_CONFIG_FILE, min, max, clamped
+    );
+    *value = clamped;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn missing_fields_keep_their_defaults() {
+        let config: GameConfig = ron::from_str("(num_rooms: 5, player_hp: 20)").unwrap();
+        assert_eq!(config.num_rooms, 5);
+        assert_eq!(config.player_hp, 20);
+        assert_eq!(config.num_monsters, GameConfig::default().num_monsters);
+    }
+
+    #[test]
+    fn values_out_of_range_are_clamped() {
+        let config: GameConfig =
+            ron::from_str("(map_width: 8, desired_floor: 1.5, player_hp: 0, num_rooms: 3)")
+                .unwrap();
+        let config = config.validated();
+        assert_eq!(config.map_width, MIN_MAP_SIZE);
+        assert_eq!(config.desired_floor, 0.6);
+        assert_eq!(config.player_hp, 1);
+        assert_eq!(config.num_rooms, 3);
+    }
+
+    #[test]
+    fn unachievable_victories_are_rejected() {
+        let config = |victory| GameConfig {
+            victory,
+            ..Default::default()
+        };
+        assert!(config(VictoryCondition::default()).check_victory().is_ok());
+        let too_deep = VictoryCondition::RetrieveArtifact {
+            artifact: "Amulet of Yala".to_string(),
+            depth: 3,
+        };
+        assert!(config(too_deep).check_victory().is_err());
+        let typo = VictoryCondition::SlayBoss {
+            boss: "Etin".to_string(),
+            depth: 2,
+        };
+        assert!(config(typo).check_victory().is_err());
+    }
+
+    #[test]
+    fn config_file_is_valid() {
+        let config: ron::Result<GameConfig> = from_reader(File::open(GAME_CONFIG_FILE).unwrap());
+        assert!(config.unwrap().check_victory().is_ok());
+    }
+}
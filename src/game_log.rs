@@ -0,0 +1,90 @@
+use serde::{Deserialize, Serialize};
+
+/// How many messages the HUD panel shows at once.
+pub const LOG_PANEL_LINES: usize = 5;
+
+/// How many messages the log keeps before dropping the oldest ones.
+pub const MAX_LOG_ENTRIES: usize = 500;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct LogEntry {
+    pub text: String,
+    pub color: (u8, u8, u8),
+}
+
+/// The messages telling the player what happened, oldest first.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct GameLog {
+    entries: Vec<LogEntry>,
+    /// How many messages the HUD panel is scrolled back from the newest one.
+    #[serde(skip)]
+    scroll: usize,
+}
+
+impl GameLog {
+    /// Appends a message, scrolling the panel back to the newest messages. Once the log is
+    /// full, the oldest message is dropped.
+    pub fn add<S: ToString>(&mut self, text: S, color: (u8, u8, u8)) {
+        if self.entries.len() >= MAX_LOG_ENTRIES {
+            self.entries.remove(0);
+        }
+        self.entries.push(LogEntry {
+            text: text.to_string(),
+            color,
+        });
+        self.scroll = 0;
+    }
+
+    pub fn entries(&self) -> &[LogEntry] {
+        &self.entries
+    }
+
+    /// Scrolls the panel `lines` messages back (positive) or forward (negative).
+    pub fn scroll_by(&mut self, lines: i32) {
+        let max_scroll = self.entries.len().saturating_sub(LOG_PANEL_LINES);
+        self.scroll = (self.scroll as i32 + lines).clamp(0, max_scroll as i32) as usize;
+    }
+
+    /// The messages currently shown in the HUD panel, oldest first.
+    pub fn panel_entries(&self) -> &[LogEntry] {
+        let end = self.entries.len() - self.scroll;
+        &self.entries[end.saturating_sub(LOG_PANEL_LINES)..end]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prelude::*;
+
+    #[test]
+    fn panel_scrolls_through_the_messages() {
+        let mut log = GameLog::default();
+        (0..8).for_each(|n| log.add(n, WHITE));
+        let texts = |log: &GameLog| -> Vec<String> {
+            log.panel_entries().iter().map(|e| e.text.clone()).collect()
+        };
+
+        assert_eq!(texts(&log), ["3", "4", "5", "6", "7"]);
+        log.scroll_by(2);
+        assert_eq!(texts(&log), ["1", "2", "3", "4", "5"]);
+        log.scroll_by(10);
+        assert_eq!(texts(&log), ["0", "1", "2", "3", "4"]);
+        log.add("new", WHITE);
+        assert_eq!(texts(&log), ["4", "5", "6", "7", "new"]);
+        log.scroll_by(-3);
+        assert_eq!(log.panel_entries().len(), LOG_PANEL_LINES);
+    }
+
+    #[test]
+    fn oldest_messages_are_dropped_when_the_log_is_full() {
+        let mut log = GameLog::default();
+        (0..MAX_LOG_ENTRIES + 3).for_each(|n| log.add(n, WHITE));
+        assert_eq!(log.entries().len(), MAX_LOG_ENTRIES);
+        assert_eq!(log.entries()[0].text, "3");
+        assert_eq!(
+            log.entries().last().unwrap().text,
+            (MAX_LOG_ENTRIES + 2).to_string()
+        );
+    }
+}
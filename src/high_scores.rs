@@ -0,0 +1,137 @@
+use crate::prelude::*;
+use ron::de::from_reader;
+use ron::ser::{to_string_pretty, PrettyConfig};
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::time::SystemTime;
+
+/// The file the best runs are kept in.
+pub const HIGH_SCORES_FILE: &str = "highscores.ron";
+
+/// How many runs the high-score table keeps.
+pub const MAX_HIGH_SCORES: usize = 10;
+
+/// A finished run, with what its score was computed from.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct HighScore {
+    pub score: u32,
+    pub seed: u64,
+    /// When the run ended, in UTC.
+    pub date: String,
+    /// The deepest dungeon level reached, counting from 1.
+    pub depth: u32,
+    pub kills: u32,
+    pub items_used: u32,
+    pub victory: bool,
+}
+
+impl HighScore {
+    pub fn new(seed: u64, date: String, stats: &RunStats, victory: bool) -> Self {
+        let depth = stats.deepest_level + 1;
+        let score = depth * 100
+            + stats.total_kills() * 10
+            + stats.items_used * 5
+            + if victory { 1000 } else { 0 };
+        Self {
+            score,
+            seed,
+            date,
+            depth,
+            kills: stats.total_kills(),
+            items_used: stats.items_used,
+            victory,
+        }
+    }
+
+    /// The score of the run played in `sim`, ending now.
+    pub fn of(sim: &Simulation) -> Self {
+        let stats = sim
+            .resources
+            .get::<RunStats>()
+            .expect("Run stats are missing");
+        Self::new(
+            sim.seed,
+            format_utc(SystemTime::now()),
+            &stats,
+            sim.turn_state() == TurnState::Victory,
+        )
+    }
+}
+
+/// The best runs played on this computer, best first.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct HighScores {
+    pub entries: Vec<HighScore>,
+}
+
+impl HighScores {
+    /// Reads the table from `HIGH_SCORES_FILE`, which is empty until a run has ended.
+    pub fn load() -> Self {
+        match File::open(HIGH_SCORES_FILE) {
+            Ok(file) => from_reader(file).unwrap_or_else(|e| {
+                println!("Warning: Unable to load {}: {}", HIGH_SCORES_FILE, e);
+                Self::default()
+            }),
+            Err(_) => Self::default(),
+        }
+    }
+
+    pub fn save(&self) -> std::io::Result<()> {
+        let text = to_string_pretty(self, PrettyConfig::default())
+            .expect("Unable to serialize the high scores");
+        std::fs::write(HIGH_SCORES_FILE, text)
+    }
+
+    /// Ranks a run among the best ones, returning its place in the table if it made it.
+    /// Ties go to the older run.
+    pub fn add(&mut self, high_score: HighScore) -> Option<usize> {
+        let rank = self
+            .entries
+            .iter()
+            .position(|entry| entry.score < high_score.score)
+            .unwrap_or(self.entries.len());
+        self.entries.insert(rank, high_score);
+        self.entries.truncate(MAX_HIGH_SCORES);
+        (rank < MAX_HIGH_SCORES).then_some(rank)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(score_stats: (u32, u32, u32), victory: bool) -> HighScore {
+        let mut stats = RunStats {
+            deepest_level: score_stats.0,
+            items_used: score_stats.2,
+            ..Default::default()
+        };
+        (0..score_stats.1).for_each(|_| stats.add_kill("Goblin"));
+        HighScore::new(1, "2024-01-01 00:00:00".to_string(), &stats, victory)
+    }
+
+    #[test]
+    fn score_rewards_depth_kills_items_and_victory() {
+        assert_eq!(run((0, 0, 0), false).score, 100);
+        assert_eq!(run((2, 3, 4), false).score, 350);
+        assert_eq!(run((2, 3, 4), true).score, 1350);
+    }
+
+    #[test]
+    fn table_keeps_the_best_runs_in_order() {
+        let mut table = HighScores::default();
+        (0..MAX_HIGH_SCORES as u32).for_each(|kills| {
+            table.add(run((0, kills, 0), false));
+        });
+        assert_eq!(table.entries[0].kills, MAX_HIGH_SCORES as u32 - 1);
+
+        assert_eq!(table.add(run((0, 0, 0), false)), None);
+        assert_eq!(table.add(run((0, 5, 0), false)), Some(5));
+        assert_eq!(table.add(run((1, 0, 0), true)), Some(0));
+        assert_eq!(table.entries.len(), MAX_HIGH_SCORES);
+        assert!(table
+            .entries
+            .windows(2)
+            .all(|pair| pair[0].score >= pair[1].score));
+    }
+}
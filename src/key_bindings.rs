@@ -0,0 +1,213 @@
+use crate::prelude::*;
+
+use ron::de::from_reader;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::File;
+
+pub const KEY_BINDINGS_FILE: &str = "resources/bindings.ron";
+
+/// What the player wants to do, independently of the key that was pressed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Action {
+    MoveNorth,
+    MoveSouth,
+    MoveEast,
+    MoveWest,
+    /// The diagonal moves, only taken when the game allows them.
+    MoveNorthEast,
+    MoveNorthWest,
+    MoveSouthEast,
+    MoveSouthWest,
+    PickUp,
+    /// Closes an open door next to the player.
+    CloseDoor,
+    /// Spends a turn looking for hidden traps and secret doors next to the player.
+    Search,
+    /// Uses the n-th carried item, starting from 0.
+    UseItem(usize),
+    Wait,
+    SaveGame,
+    /// Scrolls the message panel back to older messages, without spending the turn.
+    ScrollLogUp,
+    /// Scrolls the message panel forward to newer messages, without spending the turn.
+    ScrollLogDown,
+}
+
+/// The built in key layouts. All of them also bind the number keys to the items, `G` to
+/// pick up, `C` to close a door, `F` to search, `S` to save and `PageUp`/`PageDown` to
+/// scroll the message panel.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum KeyPreset {
+    /// Cursor keys to move, `Insert`, `Home`, `Delete` and `End` for the diagonals and
+    /// space to wait.
+    Arrows,
+    /// `hjkl` to move, `yubn` for the diagonals and `.` to wait.
+    ViKeys,
+    /// Numpad `8462` to move, `9713` for the diagonals and `5` to wait.
+    Numpad,
+}
+
+impl KeyPreset {
+    fn bindings(self) -> Vec<(VirtualKeyCode, Action)> {
+        let mut bindings = match self {
+            KeyPreset::Arrows => vec![
+                (VirtualKeyCode::Up, Action::MoveNorth),
+                (VirtualKeyCode::Down, Action::MoveSouth),
+                (VirtualKeyCode::Right, Action::MoveEast),
+                (VirtualKeyCode::Left, Action::MoveWest),
+                (VirtualKeyCode::Insert, Action::MoveNorthWest),
+                (VirtualKeyCode::Home, Action::MoveNorthEast),
+                (VirtualKeyCode::Delete, Action::MoveSouthWest),
+                (VirtualKeyCode::End, Action::MoveSouthEast),
+                (VirtualKeyCode::Space, Action::Wait),
+            ],
+            KeyPreset::ViKeys => vec![
+                (VirtualKeyCode::K, Action::MoveNorth),
+                (VirtualKeyCode::J, Action::MoveSouth),
+                (VirtualKeyCode::L, Action::MoveEast),
+                (VirtualKeyCode::H, Action::MoveWest),
+                (VirtualKeyCode::Y, Action::MoveNorthWest),
+                (VirtualKeyCode::U, Action::MoveNorthEast),
+                (VirtualKeyCode::B, Action::MoveSouthWest),
+                (VirtualKeyCode::N, Action::MoveSouthEast),
+                (VirtualKeyCode::Period, Action::Wait),
+            ],
+            KeyPreset::Numpad => vec![
+                (VirtualKeyCode::Numpad8, Action::MoveNorth),
+                (VirtualKeyCode::Numpad2, Action::MoveSouth),
+                (VirtualKeyCode::Numpad6, Action::MoveEast),
+                (VirtualKeyCode::Numpad4, Action::MoveWest),
+                (VirtualKeyCode::Numpad7, Action::MoveNorthWest),
+                (VirtualKeyCode::Numpad9, Action::MoveNorthEast),
+                (VirtualKeyCode::Numpad1, Action::MoveSouthWest),
+                (VirtualKeyCode::Numpad3, Action::MoveSouthEast),
+                (VirtualKeyCode::Numpad5, Action::Wait),
+            ],
+        };
+        bindings.extend([
+            (VirtualKeyCode::G, Action::PickUp),
+            (VirtualKeyCode::C, Action::CloseDoor),
+            (VirtualKeyCode::F, Action::Search),
+            (VirtualKeyCode::S, Action::SaveGame),
+            (VirtualKeyCode::PageUp, Action::ScrollLogUp),
+            (VirtualKeyCode::PageDown, Action::ScrollLogDown),
+            (VirtualKeyCode::Key1, Action::UseItem(0)),
+            (VirtualKeyCode::Key2, Action::UseItem(1)),
+            (VirtualKeyCode::Key3, Action::UseItem(2)),
+            (VirtualKeyCode::Key4, Action::UseItem(3)),
+            (VirtualKeyCode::Key5, Action::UseItem(4)),
+            (VirtualKeyCode::Key6, Action::UseItem(5)),
+            (VirtualKeyCode::Key7, Action::UseItem(6)),
+            (VirtualKeyCode::Key8, Action::UseItem(7)),
+            (VirtualKeyCode::Key9, Action::UseItem(8)),
+        ]);
+        bindings
+    }
+}
+
+/// The key bindings file: the presets to start from, and keys that are bound on top of them.
+#[derive(Clone, Debug, Deserialize)]
+struct KeyBindingsConfig {
+    presets: Vec<KeyPreset>,
+    #[serde(default)]
+    keys: HashMap<KeyCode, Action>,
+}
+
+/// Translates the pressed keys to player actions.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct KeyBindings {
+    bindings: HashMap<KeyCode, Action>,
+}
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        Self::from_presets(&[KeyPreset::Arrows])
+    }
+}
+
+impl KeyBindings {
+    pub fn from_presets(presets: &[KeyPreset]) -> Self {
+        Self {
+            bindings: presets
+                .iter()
+                .flat_map(|preset| preset.bindings())
+                .map(|(key, action)| (key.into(), action))
+                .collect(),
+        }
+    }
+
+    /// Loads the bindings file, falling back to the arrow keys if it can't be read.
+    pub fn load() -> Self {
+        match File::open(KEY_BINDINGS_FILE)
+            .map_err(ron::Error::from)
+            .and_then(from_reader::<_, KeyBindingsConfig>)
+        {
+            Ok(config) => {
+                let mut bindings = Self::from_presets(&config.presets);
+                bindings.bindings.extend(config.keys);
+                bindings
+            }
+            Err(e) => {
+                println!("Warning: Unable to load {}: {}", KEY_BINDINGS_FILE, e);
+                Self::default()
+            }
+        }
+    }
+
+    pub fn action(&self, key: VirtualKeyCode) -> Option<Action> {
+        self.bindings.get(&key.into()).copied()
+    }
+
+    /// The name of a key bound to `action`, to be shown to the player.
+    pub fn key_name(&self, action: Action) -> Option<String> {
+        self.bindings
+            .iter()
+            .filter(|(_, bound)| **bound == action)
+            .map(|(key, _)| format!("{:?}", key))
+            .min()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn presets_can_be_combined() {
+        let bindings = KeyBindings::from_presets(&[KeyPreset::ViKeys, KeyPreset::Numpad]);
+        assert_eq!(bindings.action(VirtualKeyCode::H), Some(Action::MoveWest));
+        assert_eq!(
+            bindings.action(VirtualKeyCode::Numpad4),
+            Some(Action::MoveWest)
+        );
+        assert_eq!(
+            bindings.action(VirtualKeyCode::Key3),
+            Some(Action::UseItem(2))
+        );
+        assert_eq!(bindings.action(VirtualKeyCode::Left), None);
+    }
+
+    #[test]
+    fn presets_bind_each_key_once() {
+        [KeyPreset::Arrows, KeyPreset::ViKeys, KeyPreset::Numpad]
+            .iter()
+            .for_each(|preset| {
+                let bindings = preset.bindings();
+                let keys: HashSet<_> = bindings.iter().map(|(key, _)| *key).collect();
+                assert_eq!(keys.len(), bindings.len(), "{:?}", preset);
+            });
+    }
+
+    #[test]
+    fn bindings_file_is_valid() {
+        let config: KeyBindingsConfig =
+            from_reader(File::open(KEY_BINDINGS_FILE).unwrap()).unwrap();
+        assert!(!config.presets.is_empty());
+        assert_eq!(
+            KeyBindings::load().action(VirtualKeyCode::G),
+            Some(Action::PickUp)
+        );
+    }
+}
@@ -0,0 +1,273 @@
+use crate::prelude::*;
+
+/// A level while it is being generated, handed to each `LevelHook` in turn.
+pub struct LevelBuild<'a> {
+    /// The depth of the level, counting from 0.
+    pub depth: u32,
+    pub map_builder: MapBuilder,
+    pub ecs: &'a mut World,
+    pub resources: &'a mut Resources,
+    pub rng: &'a mut RandomNumberGenerator,
+    pub config: &'a GameConfig,
+}
+
+/// A step run on every generated level once its architect has built the map.
+pub type LevelHook = fn(&mut LevelBuild);
+
+/// A generated level whose entities are already in the world.
+pub struct Level {
+    pub map: Map,
+    pub theme: Box<dyn MapTheme>,
+    pub player_start: Point,
+}
+
+/// Generates the levels of the dungeon: an architect builds the map, then the hooks add
+/// the features of the level in order. New level features only need a new hook.
+pub struct LevelLoader {
+    hooks: Vec<LevelHook>,
+}
+
+impl Default for LevelLoader {
+    fn default() -> Self {
+        Self::new(&[
+            place_prefab,
+            place_terrain,
+            pick_theme,
+            place_up_stairs,
+            place_goal_and_exit,
+            spawn_entities,
+            spawn_traps,
+        ])
+    }
+}
+
+impl LevelLoader {
+    pub fn new(hooks: &[LevelHook]) -> Self {
+        Self {
+            hooks: hooks.to_vec(),
+        }
+    }
+
+    /// Adds a hook, run after all the others.
+    pub fn with_hook(mut self, hook: LevelHook) -> Self {
+        self.hooks.push(hook);
+        self
+    }
+
+    /// Generates the level at `depth`, spawning its entities into `ecs`. The player is
+    /// spawned at the start of the level, or `player` is moved there if it already exists.
+    /// The game RNG is taken from `resources` for the time of the generation.
+    pub fn load(
+        &self,
+        depth: u32,
+        player: Option<Entity>,
+        ecs: &mut World,
+        resources: &mut Resources,
+        config: &GameConfig,
+    ) -> Level {
+        // The entities spawned by the hooks are flushed with the resources, so the RNG
+        // can't stay in there while the hooks also need it.
+        let mut rng = resources
+            .remove::<RandomNumberGenerator>()
+            .expect("Game RNG is missing");
+        let map_builder = MapBuilder::new(&mut rng, config);
+        let player_start = map_builder.player_start;
+        match player {
+            Some(player) => {
+                let mut entry = ecs.entry(player).expect("Player is missing");
+                entry.add_component(player_start);
+                if let Ok(player) = entry.get_component_mut::<Player>() {
+                    player.map_level = depth;
+                }
+            }
+            None => spawn_player(ecs, player_start, config),
+        }
+
+        let mut build = LevelBuild {
+            depth,
+            map_builder,
+            ecs,
+            resources,
+            rng: &mut rng,
+            config,
+        };
+        self.hooks.iter().for_each(|hook| hook(&mut build));
+        let map_builder = build.map_builder;
+        resources.insert(rng);
+
+        Level {
+            map: map_builder.map,
+            theme: map_builder.theme,
+            player_start,
+        }
+    }
+}
+
+pub fn place_prefab(build: &mut LevelBuild) {
+    build.map_builder.apply_prefab(build.rng);
+}
+
+pub fn place_terrain(build: &mut LevelBuild) {
+    build.map_builder.place_terrain(build.rng, build.depth);
+}
+
+pub fn pick_theme(build: &mut LevelBuild) {
+    build.map_builder.pick_theme(build.rng);
+}
+
+/// Puts the stairs back up under the start of every level but the first.
+pub fn place_up_stairs(build: &mut LevelBuild) {
+    if build.depth > 0 {
+        let mb = &mut build.map_builder;
+        let start_idx = mb.map.point2d_to_index(mb.player_start);
+        mb.map.tiles[start_idx] = TileType::UpStairs;
+    }
+}
+
+/// Spawns the goal of the victory condition if it lies on this level, and places the exit
+/// unless this is the deepest level. Both go as far as possible from the start, so when a
+/// level has both, the exit takes the place of a monster instead.
+pub fn place_goal_and_exit(build: &mut LevelBuild) {
+    let mb = &mut build.map_builder;
+    let has_goal = build.config.victory.goal_depth() == Some(build.depth);
+    if has_goal {
+        spawn_victory_goal(build.ecs, build.resources, mb.amulet_start, build.config);
+    }
+
+    if build.depth + 1 < build.config.dungeon_depth {
+        // With the goal on the amulet tile, the exit goes to the next farthest one.
+        let exit = if has_goal {
+            let exit = mb
+                .find_most_distant_floor(&[mb.amulet_start])
+                .expect("Level has no room for an exit");
+            mb.monster_spawns.retain(|pt| *pt != exit);
+            exit
+        } else {
+            mb.amulet_start
+        };
+        let exit_idx = mb.map.point2d_to_index(exit);
+        mb.map.tiles[exit_idx] = TileType::Exit;
+    }
+}
+
+/// Spawns the monsters and items of the level on its spawn points.
+pub fn spawn_entities(build: &mut LevelBuild) {
+    spawn_level(
+        build.ecs,
+        build.resources,
+        build.rng,
+        build.depth as usize,
+        &build.map_builder.monster_spawns,
+        build.config,
+    );
+}
+
+/// Hides the traps of the level on floor tiles of their own.
+pub fn spawn_traps(build: &mut LevelBuild) {
+    let trap_points = build
+        .map_builder
+        .trap_spawns(build.rng, build.config.num_traps);
+    spawn_level_traps(
+        build.ecs,
+        build.resources,
+        build.rng,
+        build.depth as usize,
+        &trap_points,
+        build.config,
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn load(loader: &LevelLoader, depth: u32) -> (World, Level) {
+        let mut ecs = World::default();
+        let mut resources = Resources::default();
+        resources.insert(RandomNumberGenerator::seeded(3));
+        let level = loader.load(
+            depth,
+            None,
+            &mut ecs,
+            &mut resources,
+            &GameConfig::default(),
+        );
+        assert!(resources.get::<RandomNumberGenerator>().is_some());
+        (ecs, level)
+    }
+
+    #[test]
+    fn hooks_run_in_order_after_the_architect() {
+        fn seal_the_start(build: &mut LevelBuild) {
+            let start = build.map_builder.player_start;
+            let idx = build.map_builder.map.point2d_to_index(start);
+            assert_eq!(build.map_builder.map.tiles[idx], TileType::UpStairs);
+            build.map_builder.map.tiles[idx] = TileType::Wall;
+        }
+
+        let (_, level) = load(&LevelLoader::default().with_hook(seal_the_start), 1);
+        let idx = level.map.point2d_to_index(level.player_start);
+        assert_eq!(level.map.tiles[idx], TileType::Wall);
+    }
+
+    #[test]
+    fn default_hooks_populate_the_level() {
+        let (ecs, level) = load(&LevelLoader::default(), 0);
+        let player_pos = <&Point>::query()
+            .filter(component::<Player>())
+            .iter(&ecs)
+            .next()
+            .unwrap();
+        assert_eq!(*player_pos, level.player_start);
+        assert!(level.map.tiles.contains(&TileType::Exit));
+        assert!(!level.map.tiles.contains(&TileType::UpStairs));
+        assert!(<&Enemy>::query().iter(&ecs).next().is_some());
+    }
+
+    #[test]
+    fn traps_get_tiles_of_their_own() {
+        let (ecs, _) = load(&LevelLoader::default(), 1);
+        let traps: Vec<Point> = <&Point>::query()
+            .filter(component::<Trap>())
+            .iter(&ecs)
+            .copied()
+            .collect();
+        assert_eq!(traps.len(), GameConfig::default().num_traps);
+        let others: Vec<Point> = <&Point>::query()
+            .filter(!component::<Trap>())
+            .iter(&ecs)
+            .copied()
+            .collect();
+        assert!(traps.iter().all(|pt| !others.contains(pt)));
+    }
+
+    #[test]
+    fn the_exit_and_the_goal_get_tiles_of_their_own() {
+        let config = GameConfig {
+            victory: VictoryCondition::RetrieveArtifact {
+                artifact: "Amulet of Yala".to_string(),
+                depth: 1,
+            },
+            ..Default::default()
+        };
+        let mut ecs = World::default();
+        let mut resources = Resources::default();
+        resources.insert(RandomNumberGenerator::seeded(3));
+        let level = LevelLoader::default().load(1, None, &mut ecs, &mut resources, &config);
+
+        let exit = level
+            .map
+            .tiles
+            .iter()
+            .position(|t| *t == TileType::Exit)
+            .map(|idx| level.map.index_to_point2d(idx))
+            .unwrap();
+        let amulet = <&Point>::query()
+            .filter(component::<AmuletOfYala>())
+            .iter(&ecs)
+            .next()
+            .unwrap();
+        assert_ne!(*amulet, exit);
+        assert!(<&Point>::query().iter(&ecs).all(|pos| *pos != exit));
+    }
+}
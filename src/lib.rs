@@ -0,0 +1,55 @@
+mod camera;
+mod components;
+mod dungeon;
+mod game_config;
+mod game_log;
+mod high_scores;
+mod key_bindings;
+mod key_code;
+mod level_loader;
+mod light_map;
+mod map;
+mod map_builder;
+mod menu;
+mod morgue;
+mod replay;
+mod run_stats;
+mod save_game;
+mod simulation;
+mod spawner;
+mod systems;
+mod turn_state;
+mod victory;
+
+pub mod prelude {
+    pub use bracket_lib::prelude::*;
+    pub use legion::systems::CommandBuffer;
+    pub use legion::world::SubWorld;
+    pub use legion::*;
+    pub const SCREEN_WIDTH: i32 = 80;
+    pub const SCREEN_HEIGHT: i32 = 50;
+    pub const DISPLAY_WIDTH: i32 = SCREEN_WIDTH / 2;
+    pub const DISPLAY_HEIGHT: i32 = SCREEN_HEIGHT / 2;
+    pub use crate::camera::*;
+    pub use crate::components::*;
+    pub use crate::dungeon::*;
+    pub use crate::game_config::*;
+    pub use crate::game_log::*;
+    pub use crate::high_scores::*;
+    pub use crate::key_bindings::*;
+    pub use crate::key_code::*;
+    pub use crate::level_loader::*;
+    pub use crate::light_map::*;
+    pub use crate::map::*;
+    pub use crate::map_builder::*;
+    pub use crate::menu::*;
+    pub use crate::morgue::*;
+    pub use crate::replay::*;
+    pub use crate::run_stats::*;
+    pub use crate::save_game::*;
+    pub use crate::simulation::*;
+    pub use crate::spawner::*;
+    pub use crate::systems::*;
+    pub use crate::turn_state::*;
+    pub use crate::victory::*;
+}
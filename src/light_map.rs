@@ -0,0 +1,172 @@
+use crate::prelude::*;
+
+/// How bright a tile must be for the monsters on it to be seen from afar.
+pub const LIT_THRESHOLD: f32 = 0.4;
+/// The least a tile in view is lit when drawn, so the dark parts of a level can still be
+/// told from the remembered ones.
+const MIN_VISIBLE_LIGHT: f32 = 0.35;
+/// The light of a lava tile.
+const LAVA_LIGHT: LightSource = LightSource {
+    radius: 2,
+    color: RGB {
+        r: 1.0,
+        g: 0.45,
+        b: 0.1,
+    },
+};
+
+/// The light reaching each tile of the current level: the ambient light of its theme plus
+/// the light of every light source around.
+#[derive(Clone, Debug)]
+pub struct LightMap {
+    width: i32,
+    light: Vec<RGB>,
+    /// The ambient light plus the light of the lava, which only change with the layout.
+    static_light: Vec<RGB>,
+    /// The ambient light and the tiles `static_light` was computed for.
+    static_ambient: RGB,
+    static_tiles: Vec<TileType>,
+    /// Set when the level changes, for the light to be computed before the next turn.
+    pub is_dirty: bool,
+}
+
+impl Default for LightMap {
+    fn default() -> Self {
+        Self {
+            width: 0,
+            light: Vec::new(),
+            static_light: Vec::new(),
+            static_ambient: RGB::from_f32(0.0, 0.0, 0.0),
+            static_tiles: Vec::new(),
+            is_dirty: true,
+        }
+    }
+}
+
+impl LightMap {
+    /// Lights `map` with `ambient` and the lava, then adds the light of `sources` (with
+    /// their position). The light of the lava is only computed again once the tiles of the
+    /// map have changed, as when a door is opened or a wall is blown up.
+    pub fn compute(&mut self, map: &Map, ambient: RGB, sources: &[(Point, LightSource)]) {
+        if self.static_tiles != map.tiles || self.static_ambient != ambient {
+            self.static_light = vec![ambient; map.tiles.len()];
+            map.tiles
+                .iter()
+                .enumerate()
+                .filter(|(_, tile)| **tile == TileType::Lava)
+                .for_each(|(idx, _)| {
+                    add_light(
+                        &mut self.static_light,
+                        map,
+                        map.index_to_point2d(idx),
+                        LAVA_LIGHT,
+                    )
+                });
+            self.static_ambient = ambient;
+            self.static_tiles = map.tiles.clone();
+        }
+
+        self.width = map.width;
+        self.light = self.static_light.clone();
+        sources
+            .iter()
+            .for_each(|(pos, source)| add_light(&mut self.light, map, *pos, *source));
+        self.is_dirty = false;
+    }
+
+    /// Whether the light map was computed for a map as large as `map`.
+    pub fn fits(&self, map: &Map) -> bool {
+        self.light.len() == map.tiles.len() && self.width == map.width
+    }
+
+    fn light(&self, pt: Point) -> RGB {
+        if pt.x < 0 || pt.y < 0 || pt.x >= self.width {
+            return RGB::from_f32(0.0, 0.0, 0.0);
+        }
+        self.light
+            .get((pt.y * self.width + pt.x) as usize)
+            .copied()
+            .unwrap_or_else(|| RGB::from_f32(0.0, 0.0, 0.0))
+    }
+
+    /// The brightness of the tile, between 0 and 1.
+    pub fn brightness(&self, pt: Point) -> f32 {
+        let light = self.light(pt);
+        light.r.max(light.g).max(light.b)
+    }
+
+    /// Whether what stands on the tile can be seen by `viewer`: it must be lit, or right
+    /// next to them.
+    pub fn reveals(&self, pt: Point, viewer: Point) -> bool {
+        self.brightness(pt) >= LIT_THRESHOLD || DistanceAlg::Chebyshev.distance2d(pt, viewer) <= 1.0
+    }
+
+    /// The color a tile in view, and what stands on it, is drawn with.
+    pub fn tint(&self, pt: Point) -> RGB {
+        let light = self.light(pt);
+        RGB::from_f32(
+            light.r.max(MIN_VISIBLE_LIGHT),
+            light.g.max(MIN_VISIBLE_LIGHT),
+            light.b.max(MIN_VISIBLE_LIGHT),
+        )
+    }
+}
+
+/// Adds the light given off by `source` at `pos` to the tiles of `map` it reaches.
+fn add_light(light: &mut [RGB], map: &Map, pos: Point, source: LightSource) {
+    field_of_view_set(pos, source.radius, map)
+        .iter()
+        .filter_map(|pt| map.try_idx(*pt).map(|idx| (*pt, idx)))
+        .for_each(|(pt, idx)| {
+            let distance = DistanceAlg::Pythagoras.distance2d(pos, pt);
+            let falloff = (1.0 - distance / (source.radius + 1) as f32).max(0.0);
+            let light = &mut light[idx];
+            light.r = (light.r + source.color.r * falloff).min(1.0);
+            light.g = (light.g + source.color.g * falloff).min(1.0);
+            light.b = (light.b + source.color.b * falloff).min(1.0);
+        });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn light_falls_off_with_the_distance() {
+        let map = Map::default();
+        let mut light_map = LightMap::default();
+        let torch = LightSource {
+            radius: 4,
+            color: RGB::from_f32(1.0, 1.0, 1.0),
+        };
+        light_map.compute(
+            &map,
+            RGB::from_f32(0.1, 0.1, 0.1),
+            &[(Point::new(10, 10), torch)],
+        );
+
+        let near = light_map.brightness(Point::new(11, 10));
+        let far = light_map.brightness(Point::new(13, 10));
+        assert!(near > far);
+        assert!(light_map.reveals(Point::new(11, 10), Point::new(0, 0)));
+        assert!(!light_map.reveals(Point::new(30, 30), Point::new(0, 0)));
+        assert!(light_map.reveals(Point::new(30, 30), Point::new(31, 31)));
+    }
+
+    #[test]
+    fn lava_light_follows_the_layout() {
+        let mut map = Map::default();
+        let lava = map.map_idx(10, 10);
+        map.tiles[lava] = TileType::Lava;
+        let ambient = RGB::from_f32(0.1, 0.1, 0.1);
+        let mut light_map = LightMap::default();
+
+        light_map.compute(&map, ambient, &[]);
+        assert!(light_map.brightness(Point::new(11, 10)) > 0.1);
+        light_map.compute(&map, ambient, &[]);
+        assert!(light_map.brightness(Point::new(11, 10)) > 0.1);
+        map.tiles[lava] = TileType::Floor;
+        light_map.compute(&map, ambient, &[]);
+        assert_eq!(light_map.brightness(Point::new(11, 10)), 0.1);
+    }
+}
@@ -1,212 +1,465 @@
-mod camera;
-mod components;
-mod map;
-mod map_builder;
-mod spawner;
-mod systems;
-mod turn_state;
-
-mod prelude {
-    pub use bracket_lib::prelude::*;
-    pub use legion::systems::CommandBuffer;
-    pub use legion::world::SubWorld;
-    pub use legion::*;
-    pub const SCREEN_WIDTH: i32 = 80;
-    pub const SCREEN_HEIGHT: i32 = 50;
-    pub const DISPLAY_WIDTH: i32 = SCREEN_WIDTH / 2;
-    pub const DISPLAY_HEIGHT: i32 = SCREEN_HEIGHT / 2;
-    pub use crate::camera::*;
-    pub use crate::components::*;
-    pub use crate::map::*;
-    pub use crate::map_builder::*;
-    pub use crate::spawner::*;
-    pub use crate::systems::*;
-    pub use crate::turn_state::*;
+use dungeoncrawl::prelude::*;
+
+/// Where the replay of a run is recorded when enabled from the options.
+const DEFAULT_REPLAY_FILE: &str = "replay.ron";
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum MainMenuAction {
+    NewGame,
+    Continue,
+    HighScores,
+    Options,
+    Quit,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum PauseMenuAction {
+    Resume,
+    MessageLog,
+    Statistics,
+    SaveAndQuit,
+    Options,
+    MainMenu,
+    Quit,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum EndMenuAction {
+    NewGame,
+    Statistics,
+    MainMenu,
+    Quit,
 }
 
-use prelude::*;
-use std::collections::HashSet;
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum OptionsAction {
+    RecordReplays,
+    Back,
+}
 
 struct State {
-    ecs: World,
-    resources: Resources,
-    input_systems: Schedule,
-    player_systems: Schedule,
-    monster_systems: Schedule,
+    sim: Simulation,
+    /// The config new games start with, as a continued game keeps the one it was saved with.
+    config: GameConfig,
+    recorder: Option<ReplayRecorder>,
+    playback: Option<ReplayPlayer>,
+    /// Where to record the replays of new games, if they are recorded at all.
+    record_path: Option<String>,
+    /// The seed for the next new game, when it was chosen in the command line.
+    next_seed: Option<u64>,
+    paused: bool,
+    showing_options: bool,
+    showing_high_scores: bool,
+    high_scores: HighScores,
+    /// The place of the run that just ended in the high-score table, if it made it.
+    last_rank: Option<usize>,
+    /// The full message log screen, and how many lines it is scrolled back.
+    showing_log: bool,
+    log_scroll: usize,
+    showing_stats: bool,
+    /// Why the last attempt to continue the saved game failed, shown on the main menu.
+    load_error: Option<String>,
+    /// The mouse position on the menu console (2) and whether it was clicked this frame.
+    menu_mouse: Point,
+    clicked: bool,
+    main_menu: Menu<MainMenuAction>,
+    pause_menu: Menu<PauseMenuAction>,
+    end_menu: Menu<EndMenuAction>,
+    options_menu: Menu<OptionsAction>,
 }
 
 impl State {
-    fn new() -> Self {
-        let mut ecs = World::default();
-        let mut resources = Resources::default();
-        let mut rng = RandomNumberGenerator::new();
-
-        let mut map_builder = MapBuilder::new(&mut rng);
-        spawn_player(&mut ecs, map_builder.player_start);
-        let exit_idx = map_builder.map.point2d_to_index(map_builder.amulet_start);
-        map_builder.map.tiles[exit_idx] = TileType::Exit;
-        spawn_level(
-            &mut ecs,
-            &mut resources,
-            &mut rng,
-            0,
-            &map_builder.monster_spawns,
-        );
+    fn with_simulation(sim: Simulation) -> Self {
+        Self {
+            config: sim.config.clone(),
+            sim,
+            recorder: None,
+            playback: None,
+            record_path: None,
+            next_seed: None,
+            paused: false,
+            showing_options: false,
+            showing_high_scores: false,
+            high_scores: HighScores::load(),
+            last_rank: None,
+            showing_log: false,
+            log_scroll: 0,
+            showing_stats: false,
+            load_error: None,
+            menu_mouse: Point::zero(),
+            clicked: false,
+            main_menu: Menu::new(&[
+                ("New Game", MainMenuAction::NewGame),
+                ("Continue", MainMenuAction::Continue),
+                ("High Scores", MainMenuAction::HighScores),
+                ("Options", MainMenuAction::Options),
+                ("Quit", MainMenuAction::Quit),
+            ]),
+            pause_menu: Menu::new(&[
+                ("Resume", PauseMenuAction::Resume),
+                ("Message Log", PauseMenuAction::MessageLog),
+                ("Statistics", PauseMenuAction::Statistics),
+                ("Save and Quit", PauseMenuAction::SaveAndQuit),
+                ("Options", PauseMenuAction::Options),
+                ("Abandon Run", PauseMenuAction::MainMenu),
+                ("Quit", PauseMenuAction::Quit),
+            ]),
+            end_menu: Menu::new(&[
+                ("Play Again", EndMenuAction::NewGame),
+                ("Statistics", EndMenuAction::Statistics),
+                ("Main Menu", EndMenuAction::MainMenu),
+                ("Quit", EndMenuAction::Quit),
+            ]),
+            options_menu: Menu::new(&[
+                ("Record replays", OptionsAction::RecordReplays),
+                ("Back", OptionsAction::Back),
+            ]),
+        }
+    }
 
-        resources.insert(map_builder.map);
-        resources.insert(Camera::new(map_builder.player_start));
-        resources.insert(TurnState::AwaitingInput);
-        resources.insert(map_builder.theme);
+    /// Opens the main menu, with the first new game using `seed`.
+    fn new(seed: u64) -> Result<Self, String> {
+        let mut state = Self::with_simulation(Simulation::new(seed)?);
+        state.next_seed = Some(seed);
+        state.sim.set_turn_state(TurnState::MainMenu);
+        Ok(state)
+    }
 
-        Self {
-            ecs,
-            resources,
-            input_systems: build_input_scheduler(),
-            player_systems: build_player_scheduler(),
-            monster_systems: build_monster_scheduler(),
+    /// Resumes the saved game, or opens the main menu showing why it couldn't be resumed.
+    fn load(seed: u64) -> Result<Self, String> {
+        let mut state = Self::new(seed)?;
+        state.continue_game();
+        Ok(state)
+    }
+
+    /// Replaces the current game by the saved one. The save is only deleted once the game is
+    /// restored, and is kept with the error shown on the main menu if it can't be.
+    fn continue_game(&mut self) {
+        let restored = load_game()
+            .map_err(|e| e.to_string())
+            .and_then(|(ecs, resources, seed)| Simulation::from_world(seed, ecs, resources));
+        match restored {
+            Ok(sim) => {
+                self.sim = sim;
+                self.recorder = None;
+                self.load_error = None;
+                if let Err(e) = delete_saved_game() {
+                    println!("Unable to delete the saved game: {}", e);
+                }
+            }
+            Err(e) => {
+                println!("Unable to load the saved game: {}", e);
+                self.load_error = Some(format!("Unable to load the saved game: {}", e));
+            }
+        }
+    }
+
+    fn replay(replay: &Replay, speed: usize) -> Self {
+        let mut state = Self::with_simulation(Simulation::with_settings(
+            replay.seed,
+            true,
+            replay.config.clone(),
+            replay.key_bindings.clone(),
+        ));
+        state.playback = Some(ReplayPlayer::new(replay, speed));
+        state
+    }
+
+    fn save_and_quit(&mut self, ctx: &mut BTerm) {
+        save_game(&self.sim.ecs, &self.sim.resources, self.sim.seed)
+            .expect("Unable to save the game");
+        ctx.quitting = true;
+    }
+
+    fn main_menu(&mut self, ctx: &mut BTerm, key: Option<VirtualKeyCode>) {
+        ctx.set_active_console(2);
+        ctx.print_color_centered(20, YELLOW, BLACK, "Dungeon Crawler");
+        ctx.print_color_centered(22, WHITE, BLACK, self.config.victory.quest());
+
+        if let Some(error) = &self.load_error {
+            ctx.print_color_centered(25, RED, BLACK, error);
+        }
+
+        self.main_menu
+            .set_enabled(MainMenuAction::Continue, has_saved_game());
+        self.main_menu.render(ctx, 28);
+        match self
+            .main_menu
+            .update(key, self.menu_mouse, self.clicked, 28)
+        {
+            Some(MainMenuAction::NewGame) => {
+                let seed = self.next_seed.take().unwrap_or_else(new_seed);
+                self.start_new_game(seed);
+            }
+            Some(MainMenuAction::Continue) => self.continue_game(),
+            Some(MainMenuAction::HighScores) => self.showing_high_scores = true,
+            Some(MainMenuAction::Options) => self.showing_options = true,
+            Some(MainMenuAction::Quit) => ctx.quitting = true,
+            None => {
+                if let Some(VirtualKeyCode::Escape) = key {
+                    ctx.quitting = true;
+                }
+            }
         }
     }
 
-    fn game_over(&mut self, ctx: &mut BTerm) {
+    fn pause_menu(&mut self, ctx: &mut BTerm, key: Option<VirtualKeyCode>) {
         ctx.set_active_console(2);
-        ctx.print_color_centered(2, RED, BLACK, "Your quest has ended.");
+        ctx.print_color_centered(30, YELLOW, BLACK, "Paused");
+        self.pause_menu.render(ctx, 34);
+        match self
+            .pause_menu
+            .update(key, self.menu_mouse, self.clicked, 34)
+        {
+            Some(PauseMenuAction::Resume) => self.paused = false,
+            Some(PauseMenuAction::MessageLog) => {
+                self.showing_log = true;
+                self.log_scroll = 0;
+            }
+            Some(PauseMenuAction::Statistics) => self.showing_stats = true,
+            Some(PauseMenuAction::SaveAndQuit) => self.save_and_quit(ctx),
+            Some(PauseMenuAction::Options) => self.showing_options = true,
+            Some(PauseMenuAction::MainMenu) => {
+                self.paused = false;
+                self.sim.set_turn_state(TurnState::MainMenu);
+            }
+            Some(PauseMenuAction::Quit) => ctx.quitting = true,
+            None => {
+                if let Some(VirtualKeyCode::Escape) = key {
+                    self.paused = false;
+                }
+            }
+        }
+    }
+
+    /// Shows every message of the run, newest at the bottom.
+    fn message_log(&mut self, ctx: &mut BTerm, key: Option<VirtualKeyCode>) {
+        const TOP_Y: i32 = 4;
+        let lines = (SCREEN_HEIGHT * 2 - TOP_Y - 3) as usize;
+
+        ctx.set_active_console(2);
+        ctx.cls();
+        ctx.print_color_centered(1, YELLOW, BLACK, "Message Log");
         ctx.print_color_centered(
-            4,
-            WHITE,
+            SCREEN_HEIGHT * 2 - 2,
+            GRAY,
             BLACK,
-            "Slain by a monster, your hero's journey has come to a premature end.",
+            "Up/Down and PageUp/PageDown to scroll, Escape to go back",
         );
+
+        let log = self.sim.resources.get::<GameLog>().unwrap();
+        let entries = log.entries();
+        let max_scroll = entries.len().saturating_sub(lines);
+        self.log_scroll = match key {
+            Some(VirtualKeyCode::Up) => self.log_scroll + 1,
+            Some(VirtualKeyCode::Down) => self.log_scroll.saturating_sub(1),
+            Some(VirtualKeyCode::PageUp) => self.log_scroll + lines,
+            Some(VirtualKeyCode::PageDown) => self.log_scroll.saturating_sub(lines),
+            _ => self.log_scroll,
+        }
+        .min(max_scroll);
+
+        let end = entries.len() - self.log_scroll;
+        entries[end.saturating_sub(lines)..end]
+            .iter()
+            .enumerate()
+            .for_each(|(n, entry)| {
+                ctx.print_color(2, TOP_Y + n as i32, entry.color, BLACK, &entry.text);
+            });
+        drop(log);
+
+        if let Some(VirtualKeyCode::Escape) = key {
+            self.showing_log = false;
+        }
+    }
+
+    /// Draws the high-score table from row `top_y`, highlighting the run at `highlight`.
+    fn render_high_scores(&self, ctx: &mut BTerm, top_y: i32, highlight: Option<usize>) {
+        ctx.print_color_centered(top_y, YELLOW, BLACK, "High Scores");
+        if self.high_scores.entries.is_empty() {
+            ctx.print_color_centered(top_y + 2, GRAY, BLACK, "No run has ended yet.");
+            return;
+        }
         ctx.print_color_centered(
-            5,
-            WHITE,
+            top_y + 2,
+            GRAY,
             BLACK,
-            "The Amulet of Yala remains unclaimed, and your home town is not saved.",
+            format!(
+                "{:>2}  {:>6}  {:>5}  {:>5}  {:>5}  {:<7}  {:>20}  {:<19}",
+                "#", "Score", "Depth", "Kills", "Items", "Result", "Seed", "Date (UTC)"
+            ),
         );
+        self.high_scores
+            .entries
+            .iter()
+            .enumerate()
+            .for_each(|(n, entry)| {
+                let color = if Some(n) == highlight { YELLOW } else { WHITE };
+                let result = if entry.victory { "Victory" } else { "Died" };
+                ctx.print_color_centered(
+                    top_y + 3 + n as i32,
+                    color,
+                    BLACK,
+                    format!(
+                        "{:>2}  {:>6}  {:>5}  {:>5}  {:>5}  {:<7}  {:>20}  {:<19}",
+                        n + 1,
+                        entry.score,
+                        entry.depth,
+                        entry.kills,
+                        entry.items_used,
+                        result,
+                        entry.seed,
+                        entry.date
+                    ),
+                );
+            });
+    }
+
+    fn high_scores_screen(&mut self, ctx: &mut BTerm, key: Option<VirtualKeyCode>) {
+        ctx.set_active_console(2);
+        self.render_high_scores(ctx, 20, None);
         ctx.print_color_centered(
-            8,
-            YELLOW,
+            SCREEN_HEIGHT * 2 - 2,
+            GRAY,
             BLACK,
-            "Don't worry, you can always try again with a new hero.",
+            "Start a new game with --seed <seed> to replay a run. Escape to go back.",
         );
-        ctx.print_color_centered(9, GREEN, BLACK, "Press 1 to play again.");
+        if let Some(VirtualKeyCode::Escape) = key {
+            self.showing_high_scores = false;
+        }
+    }
 
-        if let Some(VirtualKeyCode::Key1) = ctx.key {
-            self.reset_game_state();
-        } else if let Some(VirtualKeyCode::Escape) = ctx.key {
-            ctx.quitting = true;
+    /// Shows the statistics of the current run.
+    fn run_stats(&mut self, ctx: &mut BTerm, key: Option<VirtualKeyCode>) {
+        ctx.set_active_console(2);
+        ctx.cls();
+        ctx.print_color_centered(1, YELLOW, BLACK, "Statistics");
+        self.sim
+            .stats_summary()
+            .iter()
+            .enumerate()
+            .for_each(|(n, line)| ctx.print_color(60, 4 + n as i32, WHITE, BLACK, line));
+        ctx.print_color_centered(SCREEN_HEIGHT * 2 - 2, GRAY, BLACK, "Escape to go back");
+
+        if let Some(VirtualKeyCode::Escape) = key {
+            self.showing_stats = false;
         }
     }
 
-    fn victory(&mut self, ctx: &mut BTerm) {
+    fn options_menu(&mut self, ctx: &mut BTerm, key: Option<VirtualKeyCode>) {
         ctx.set_active_console(2);
-        ctx.print_color_centered(2, GREEN, BLACK, "You have won!");
+        ctx.print_color_centered(30, YELLOW, BLACK, "Options");
+        let record_label = match &self.record_path {
+            Some(path) => format!("Record replays of new games: On ({})", path),
+            None => "Record replays of new games: Off".to_string(),
+        };
+        self.options_menu
+            .set_label(OptionsAction::RecordReplays, &record_label);
+        self.options_menu.render(ctx, 34);
+        match self
+            .options_menu
+            .update(key, self.menu_mouse, self.clicked, 34)
+        {
+            Some(OptionsAction::RecordReplays) => {
+                self.record_path = match self.record_path {
+                    Some(_) => None,
+                    None => Some(DEFAULT_REPLAY_FILE.to_string()),
+                }
+            }
+            Some(OptionsAction::Back) => self.showing_options = false,
+            None => {
+                if let Some(VirtualKeyCode::Escape) = key {
+                    self.showing_options = false;
+                }
+            }
+        }
+    }
+
+    /// The choices shown below the game over and victory texts.
+    fn end_menu(&mut self, ctx: &mut BTerm, key: Option<VirtualKeyCode>, top_y: i32) {
+        self.end_menu.render(ctx, top_y);
+        match self
+            .end_menu
+            .update(key, self.menu_mouse, self.clicked, top_y)
+        {
+            Some(EndMenuAction::NewGame) => self.start_new_game(new_seed()),
+            Some(EndMenuAction::Statistics) => self.showing_stats = true,
+            Some(EndMenuAction::MainMenu) => self.sim.set_turn_state(TurnState::MainMenu),
+            Some(EndMenuAction::Quit) => ctx.quitting = true,
+            None => {
+                if let Some(VirtualKeyCode::Escape) = key {
+                    ctx.quitting = true;
+                }
+            }
+        }
+    }
+
+    fn game_over(&mut self, ctx: &mut BTerm, key: Option<VirtualKeyCode>) {
+        ctx.set_active_console(2);
+        ctx.print_color_centered(2, RED, BLACK, "Your quest has ended.");
         ctx.print_color_centered(
             4,
             WHITE,
             BLACK,
-            "You put on the Amulet of Yala and feel its power course through your veins.",
+            "Slain by a monster, your hero's journey has come to a premature end.",
         );
+        ctx.print_color_centered(5, WHITE, BLACK, self.sim.config.victory.failure());
+        ctx.print_color_centered(
+            8,
+            YELLOW,
+            BLACK,
+            "Don't worry, you can always try again with a new hero.",
+        );
+        ctx.print_color_centered(10, GRAY, BLACK, format!("Seed: {}", self.sim.seed));
+        self.end_menu(ctx, key, 13);
+        self.render_high_scores(ctx, 22, self.last_rank);
+    }
+
+    fn victory(&mut self, ctx: &mut BTerm, key: Option<VirtualKeyCode>) {
+        ctx.set_active_console(2);
+        ctx.print_color_centered(2, GREEN, BLACK, "You have won!");
+        ctx.print_color_centered(4, WHITE, BLACK, self.sim.config.victory.accomplishment());
         ctx.print_color_centered(
             5,
             WHITE,
             BLACK,
             "Your town is saved, and you can return to your normal life.",
         );
-        ctx.print_color_centered(7, GREEN, BLACK, "Press 1 to play again.");
-
-        if let Some(VirtualKeyCode::Key1) = ctx.key {
-            self.reset_game_state();
-        } else if let Some(VirtualKeyCode::Escape) = ctx.key {
-            ctx.quitting = true;
-        }
+        ctx.print_color_centered(8, GRAY, BLACK, format!("Seed: {}", self.sim.seed));
+        self.end_menu(ctx, key, 11);
+        self.render_high_scores(ctx, 20, self.last_rank);
     }
 
-    fn reset_game_state(&mut self) {
-        self.ecs = World::default();
-        self.resources = Resources::default();
-        let mut rng = RandomNumberGenerator::new();
-        let mut map_builder = MapBuilder::new(&mut rng);
-        spawn_player(&mut self.ecs, map_builder.player_start);
-        let exit_idx = map_builder.map.point2d_to_index(map_builder.amulet_start);
-        map_builder.map.tiles[exit_idx] = TileType::Exit;
-        spawn_level(
-            &mut self.ecs,
-            &mut &mut self.resources,
-            &mut rng,
-            0,
-            &map_builder.monster_spawns,
-        );
-        self.resources.insert(map_builder.map);
-        self.resources.insert(Camera::new(map_builder.player_start));
-        self.resources.insert(TurnState::AwaitingInput);
-        self.resources.insert(map_builder.theme);
+    fn start_new_game(&mut self, seed: u64) {
+        println!("Starting new game with seed {}", seed);
+        self.sim.config = self.config.clone();
+        self.sim.reset(seed);
+        self.load_error = None;
+        self.last_rank = None;
+        self.recorder = self
+            .record_path
+            .as_ref()
+            .map(|path| ReplayRecorder::new(path, seed));
     }
 
-    fn advance_level(&mut self) {
-        let player_entity = *<Entity>::query()
-            .filter(component::<Player>())
-            .iter(&self.ecs)
-            .next()
-            .unwrap();
-
-        let mut entities_to_keep = HashSet::new();
-        entities_to_keep.insert(player_entity);
-
-        <(Entity, &Carried)>::query()
-            .iter(&self.ecs)
-            .filter(|(_, carry)| carry.0 == player_entity)
-            .map(|(e, _)| *e)
-            .for_each(|e| {
-                entities_to_keep.insert(e);
-            });
-
-        let mut cb = CommandBuffer::new(&self.ecs);
-        for e in Entity::query().iter(&self.ecs) {
-            if !entities_to_keep.contains(e) {
-                cb.remove(*e);
-            }
+    /// Writes the morgue file of the run that just ended and ranks it in the high scores.
+    fn end_run(&mut self) {
+        // A save made during the run would let it be continued and scored again.
+        if let Err(e) = delete_saved_game() {
+            println!("Unable to delete the saved game: {}", e);
         }
-        cb.flush(&mut self.ecs, &mut self.resources);
-
-        <&mut FieldOfView>::query()
-            .iter_mut(&mut self.ecs)
-            .for_each(|fov| fov.is_dirty = true);
-
-        let mut rng = RandomNumberGenerator::new();
-        let mut map_builder = MapBuilder::new(&mut rng);
-
-        let mut map_level = 0;
-        <(&mut Player, &mut Point)>::query()
-            .iter_mut(&mut self.ecs)
-            .for_each(|(player, pos)| {
-                player.map_level += 1;
-                map_level = player.map_level;
-                pos.x = map_builder.player_start.x;
-                pos.y = map_builder.player_start.y;
-            });
-
-        if map_level == 2 {
-            spawn_amulet_of_yala(&mut self.ecs, map_builder.amulet_start);
-        } else {
-            let exit_idx = map_builder.map.point2d_to_index(map_builder.amulet_start);
-            map_builder.map.tiles[exit_idx] = TileType::Exit;
+        match write_morgue(&self.sim) {
+            Ok(path) => println!("Morgue file written to {}", path.display()),
+            Err(e) => println!("Unable to write the morgue file: {}", e),
+        }
+        self.last_rank = self.high_scores.add(HighScore::of(&self.sim));
+        if let Err(e) = self.high_scores.save() {
+            println!("Unable to save the high scores: {}", e);
         }
-        spawn_level(
-            &mut self.ecs,
-            &mut self.resources,
-            &mut rng,
-            map_level as usize,
-            &map_builder.monster_spawns,
-        );
-        self.resources.insert(map_builder.map);
-        self.resources.insert(Camera::new(map_builder.player_start));
-        self.resources.insert(TurnState::AwaitingInput);
-        self.resources.insert(map_builder.theme);
     }
-}
 
-impl GameState for State {
-    fn tick(&mut self, ctx: &mut BTerm) {
+    /// Runs one frame of the game with the given input and renders it.
+    fn run_frame(&mut self, ctx: &mut BTerm, key: Option<VirtualKeyCode>, mouse_pos: Point) {
         ctx.set_active_console(0);
         ctx.cls();
         ctx.set_active_console(1);
@@ -214,34 +467,205 @@ impl GameState for State {
         ctx.set_active_console(2);
         ctx.cls();
 
-        self.resources.insert(ctx.key);
         ctx.set_active_console(0);
-        self.resources.insert(Point::from_tuple(ctx.mouse_pos()));
-
-        let current_state = *self.resources.get::<TurnState>().unwrap();
-        match current_state {
-            TurnState::AwaitingInput => self
-                .input_systems
-                .execute(&mut self.ecs, &mut self.resources),
-            TurnState::PlayerTurn => self
-                .player_systems
-                .execute(&mut self.ecs, &mut self.resources),
-            TurnState::MonsterTurn => self
-                .monster_systems
-                .execute(&mut self.ecs, &mut self.resources),
-            TurnState::GameOver => self.game_over(ctx),
-            TurnState::Victory => self.victory(ctx),
-            TurnState::NextLevel => self.advance_level(),
+        let was_over = matches!(
+            self.sim.turn_state(),
+            TurnState::GameOver | TurnState::Victory
+        );
+        self.sim.tick(key, mouse_pos);
+        if let Some(recorder) = &mut self.recorder {
+            recorder
+                .record(key, mouse_pos, &self.sim)
+                .expect("Unable to write the replay");
+        }
+        let is_over = matches!(
+            self.sim.turn_state(),
+            TurnState::GameOver | TurnState::Victory
+        );
+        if is_over && !was_over && self.playback.is_none() {
+            self.end_run();
+        }
+
+        match self.sim.turn_state() {
+            TurnState::GameOver => self.game_over(ctx, key),
+            TurnState::Victory => self.victory(ctx, key),
+            TurnState::SaveGame => self.save_and_quit(ctx),
+            _ => {}
         }
 
         render_draw_buffer(ctx).expect("Render error");
     }
+
+    /// Feeds the next frames of the replay being played back, if any. Returns false once
+    /// the replay is over, handing the control back to the player.
+    fn play_back(&mut self, ctx: &mut BTerm) -> bool {
+        let Some(playback) = &mut self.playback else {
+            return false;
+        };
+        match ctx.key {
+            Some(VirtualKeyCode::Equals) | Some(VirtualKeyCode::Plus) => playback.speed += 1,
+            Some(VirtualKeyCode::Minus) => playback.speed = usize::max(1, playback.speed - 1),
+            _ => {}
+        }
+
+        for _ in 0..playback.speed {
+            let Some((key, mouse_pos)) = self.playback.as_mut().and_then(|p| p.next_input()) else {
+                break;
+            };
+            self.run_frame(ctx, key, mouse_pos);
+        }
+        if self.playback.as_ref().is_some_and(|p| p.is_finished()) {
+            println!("Replay finished, the game is now under your control.");
+            self.playback = None;
+        }
+        true
+    }
+}
+
+impl GameState for State {
+    fn tick(&mut self, ctx: &mut BTerm) {
+        ctx.set_active_console(2);
+        self.menu_mouse = Point::from_tuple(ctx.mouse_pos());
+        self.clicked = ctx.left_click;
+
+        if self.play_back(ctx) {
+            return;
+        }
+
+        let in_main_menu = self.sim.turn_state() == TurnState::MainMenu;
+        if self.showing_log {
+            self.message_log(ctx, ctx.key);
+        } else if self.showing_stats {
+            self.run_stats(ctx, ctx.key);
+        } else if self.showing_options || self.showing_high_scores || self.paused || in_main_menu {
+            // The map is kept on screen behind the pause and options menus during a run.
+            if in_main_menu {
+                ctx.set_active_console(0);
+                ctx.cls();
+                ctx.set_active_console(1);
+                ctx.cls();
+            }
+            ctx.set_active_console(2);
+            ctx.cls();
+
+            if self.showing_options {
+                self.options_menu(ctx, ctx.key);
+            } else if self.showing_high_scores {
+                self.high_scores_screen(ctx, ctx.key);
+            } else if self.paused {
+                self.pause_menu(ctx, ctx.key);
+            } else {
+                self.main_menu(ctx, ctx.key);
+            }
+        } else if ctx.key == Some(VirtualKeyCode::Escape)
+            && self.sim.turn_state() == TurnState::AwaitingInput
+        {
+            self.paused = true;
+        } else {
+            ctx.set_active_console(0);
+            let mouse_pos = Point::from_tuple(ctx.mouse_pos());
+            self.run_frame(ctx, ctx.key, mouse_pos);
+        }
+    }
+}
+
+/// Returns a fresh seed for a new run.
+fn new_seed() -> u64 {
+    RandomNumberGenerator::new().next_u64()
+}
+
+/// Returns the value given after `flag` in the command line, if any.
+fn arg_value<'a>(args: &'a [String], flag: &str) -> Option<&'a String> {
+    let pos = args.iter().position(|arg| arg == flag)?;
+    args.get(pos + 1).filter(|arg| !arg.starts_with("--"))
+}
+
+/// Reads the game seed from the `--seed <number>` command line argument,
+/// falling back to a random one when it is not given.
+fn parse_seed(args: &[String]) -> u64 {
+    match arg_value(args, "--seed") {
+        Some(seed) => seed.parse().expect("--seed expects a non-negative integer"),
+        None => new_seed(),
+    }
+}
+
+/// Plays a replay without a window and checks it reaches the recorded end state.
+fn verify_replay(replay: &Replay) -> BError {
+    let outcome = ReplayOutcome::of(&replay.play_headless());
+    println!("Replay ended with {:?}", outcome);
+    match replay.outcome {
+        Some(expected) if expected != outcome => {
+            Err(format!("Replay diverged, expected {:?}", expected).into())
+        }
+        Some(_) => {
+            println!("Replay matches the recorded run.");
+            Ok(())
+        }
+        None => Ok(()),
+    }
+}
+
+/// Prints a generated map to stdout instead of starting the game. The architect can be
+/// forced by passing its name after `--dump-map`, otherwise it is picked as in the game.
+fn dump_map(args: &[String], seed: u64) -> BError {
+    let mut rng = RandomNumberGenerator::seeded(seed);
+    let config = GameConfig::load()?;
+    let mut mb = match arg_value(args, "--dump-map") {
+        Some(architect) => MapBuilder::with_architect(architect.parse()?, &mut rng, &config),
+        None => MapBuilder::new(&mut rng, &config),
+    };
+    mb.apply_prefab(&mut rng);
+    mb.place_terrain(&mut rng, 0);
+
+    println!("Seed: {}", seed);
+    println!(
+        "Legend: @ player start, A amulet/exit, M monster spawn, + door, S secret door, \
+         ~/= shallow/deep water, * lava, : rubble, %/, prefab wall/floor"
+    );
+    print!("{}", mb.to_ascii());
+    Ok(())
 }
 
-// TODO: add mode to use the map print mode from here:
-//       https://github.com/thebracket/HandsOnRust/blob/main/MoreInterestingDungeons/output_harness/src/map_builder/mod.rs#L68
-//       to test generated maps. Can be a cmd line argument/switch.
 fn main() -> BError {
+    let args: Vec<String> = std::env::args().collect();
+    if args.iter().any(|arg| arg == "--dump-map") {
+        return dump_map(&args, parse_seed(&args));
+    }
+
+    let replay = match arg_value(&args, "--replay") {
+        Some(path) => Some(Replay::load(path)?),
+        None => None,
+    };
+    let headless = args.iter().any(|arg| arg == "--headless");
+    if let (Some(replay), true) = (&replay, headless) {
+        return verify_replay(replay);
+    }
+
+    let continue_game = args.iter().any(|arg| arg == "--continue");
+    let mut state = if let Some(replay) = &replay {
+        let speed = arg_value(&args, "--speed")
+            .map(|speed| {
+                speed
+                    .parse()
+                    .ok()
+                    .filter(|speed| *speed > 0)
+                    .expect("--speed expects a positive integer")
+            })
+            .unwrap_or(1);
+        State::replay(replay, speed)
+    } else if continue_game && has_saved_game() {
+        State::load(parse_seed(&args))?
+    } else {
+        State::new(parse_seed(&args))?
+    };
+    if let Some(path) = arg_value(&args, "--record") {
+        if continue_game {
+            println!("A continued game can't be replayed from its seed, so it is not recorded.");
+        } else {
+            state.record_path = Some(path.clone());
+        }
+    }
+
     let context = BTermBuilder::new()
         .with_title("Dungeon Crawler")
         .with_fps_cap(30.0)
@@ -254,5 +678,5 @@ fn main() -> BError {
         .with_simple_console_no_bg(DISPLAY_WIDTH, DISPLAY_HEIGHT, "dungeonfont.png")
         .with_simple_console_no_bg(SCREEN_WIDTH * 2, SCREEN_HEIGHT * 2, "terminal8x8.png")
         .build()?;
-    main_loop(context, State::new())
+    main_loop(context, state)
 }
@@ -1,53 +1,203 @@
 use crate::prelude::*;
+use serde::{Deserialize, Serialize};
 
-const NUM_TILES: usize = (SCREEN_WIDTH * SCREEN_HEIGHT) as usize;
-
-#[derive(Copy, Clone, Debug, PartialEq)]
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub enum TileType {
     Wall,
     Floor,
     Exit,
+    UpStairs,
+    /// Blocks sight and movement until it is opened by bumping into it.
+    DoorClosed,
+    DoorOpen,
+    /// A closed door that looks like a wall until it is found by searching.
+    DoorSecret,
+    /// Takes twice as long to wade through.
+    ShallowWater,
+    /// Too deep to wade through, it blocks movement but not sight.
+    DeepWater,
+    /// Burns whoever steps onto it.
+    Lava,
+    /// Takes twice as long to climb over.
+    Rubble,
+}
+
+/// The steps to the neighbours of a tile: the four cardinal ones, then the diagonals.
+pub const DIRECTIONS: [Point; 8] = [
+    Point::constant(-1, 0),
+    Point::constant(1, 0),
+    Point::constant(0, -1),
+    Point::constant(0, 1),
+    Point::constant(-1, -1),
+    Point::constant(1, -1),
+    Point::constant(-1, 1),
+    Point::constant(1, 1),
+];
+
+/// How much ground a step covers: diagonal steps are longer, so paths prefer the straight
+/// ones.
+pub fn step_length(delta: Point) -> f32 {
+    if delta.x != 0 && delta.y != 0 {
+        1.4
+    } else {
+        1.0
+    }
+}
+
+/// The damage taken by stepping onto lava.
+pub const LAVA_DAMAGE: i32 = 3;
+
+impl TileType {
+    /// The character standing for the tile in the plain text dumps of the map.
+    pub fn to_ascii(self) -> char {
+        match self {
+            TileType::Wall => '#',
+            TileType::Floor => '.',
+            TileType::Exit => '>',
+            TileType::UpStairs => '<',
+            TileType::DoorClosed => '+',
+            TileType::DoorOpen => '\'',
+            TileType::DoorSecret => 'S',
+            TileType::ShallowWater => '~',
+            TileType::DeepWater => '=',
+            TileType::Lava => '*',
+            TileType::Rubble => ':',
+        }
+    }
 }
 
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Map {
+    pub width: i32,
+    pub height: i32,
     pub tiles: Vec<TileType>,
     pub revealed_tiles: Vec<bool>,
-}
-
-pub fn map_idx(x: i32, y: i32) -> usize {
-    ((y * SCREEN_WIDTH) + x) as usize
+    /// Whether the paths can take diagonal steps.
+    #[serde(default)]
+    pub diagonal_movement: bool,
 }
 
 impl Map {
-    pub fn new() -> Self {
+    pub fn new(width: i32, height: i32) -> Self {
+        let num_tiles = (width * height) as usize;
         Self {
-            tiles: vec![TileType::Floor; NUM_TILES],
-            revealed_tiles: vec![false; NUM_TILES],
+            width,
+            height,
+            tiles: vec![TileType::Floor; num_tiles],
+            revealed_tiles: vec![false; num_tiles],
+            diagonal_movement: false,
         }
     }
 
+    pub fn map_idx(&self, x: i32, y: i32) -> usize {
+        ((y * self.width) + x) as usize
+    }
+
+    pub fn center(&self) -> Point {
+        Point::new(self.width / 2, self.height / 2)
+    }
+
     pub fn in_bounds(&self, point: Point) -> bool {
-        point.x >= 0 && point.x < SCREEN_WIDTH && point.y >= 0 && point.y < SCREEN_HEIGHT
+        point.x >= 0 && point.x < self.width && point.y >= 0 && point.y < self.height
     }
 
     pub fn try_idx(&self, point: Point) -> Option<usize> {
         if !self.in_bounds(point) {
             None
         } else {
-            Some(map_idx(point.x, point.y))
+            Some(self.map_idx(point.x, point.y))
         }
     }
 
     pub fn can_enter_tile(&self, point: Point) -> bool {
-        self.in_bounds(point) && self.tiles[map_idx(point.x, point.y)] == TileType::Floor
-            || self.in_bounds(point) && self.tiles[map_idx(point.x, point.y)] == TileType::Exit
+        self.in_bounds(point)
+            && matches!(
+                self.tiles[self.map_idx(point.x, point.y)],
+                TileType::Floor
+                    | TileType::Exit
+                    | TileType::UpStairs
+                    | TileType::DoorOpen
+                    | TileType::ShallowWater
+                    | TileType::Lava
+                    | TileType::Rubble
+            )
+    }
+
+    /// The energy it takes to step onto the tile.
+    pub fn move_cost(&self, idx: usize) -> i32 {
+        match self.tiles[idx] {
+            TileType::ShallowWater | TileType::Rubble => ACTION_COST * 2,
+            _ => ACTION_COST,
+        }
+    }
+
+    /// The cost of stepping onto the tile for the pathing: slow tiles cost as much as the
+    /// time they take, and paths go a long way around lava.
+    pub fn path_cost(&self, idx: usize) -> f32 {
+        match self.tiles[idx] {
+            TileType::Lava => 10.0,
+            _ => self.move_cost(idx) as f32 / ACTION_COST as f32,
+        }
+    }
+
+    pub fn is_closed_door(&self, point: Point) -> bool {
+        self.try_idx(point)
+            .is_some_and(|idx| self.tiles[idx] == TileType::DoorClosed)
+    }
+
+    /// Whether paths can go through the tile: closed doors block movement, but they are
+    /// opened on the way.
+    pub fn is_passable(&self, point: Point) -> bool {
+        self.can_enter_tile(point) || self.is_closed_door(point)
+    }
+
+    /// Whether the tile is on the edge of the map, whose walls can't be dug or destroyed.
+    pub fn is_border(&self, point: Point) -> bool {
+        point.x == 0 || point.y == 0 || point.x == self.width - 1 || point.y == self.height - 1
+    }
+
+    /// Whether the tile is a wall that can be dug through or blown up.
+    pub fn can_dig(&self, point: Point) -> bool {
+        !self.is_border(point)
+            && self
+                .try_idx(point)
+                .is_some_and(|idx| self.tiles[idx] == TileType::Wall)
+    }
+
+    /// The steps allowed from one tile to the next.
+    pub fn directions(&self) -> &'static [Point] {
+        if self.diagonal_movement {
+            &DIRECTIONS
+        } else {
+            &DIRECTIONS[..4]
+        }
+    }
+
+    /// Whether the layout allows a step of `delta` from `loc`: a diagonal step can't squeeze
+    /// between two walls, nor go in or out of a doorway.
+    pub fn can_step(&self, loc: Point, delta: Point) -> bool {
+        if delta.x == 0 || delta.y == 0 {
+            return true;
+        }
+        let is_wall = |pt| {
+            self.try_idx(pt)
+                .is_none_or(|idx| matches!(self.tiles[idx], TileType::Wall | TileType::DoorSecret))
+        };
+        let is_door = |pt| {
+            self.try_idx(pt).is_some_and(|idx| {
+                matches!(self.tiles[idx], TileType::DoorClosed | TileType::DoorOpen)
+            })
+        };
+        let squeezes =
+            is_wall(loc + Point::new(delta.x, 0)) && is_wall(loc + Point::new(0, delta.y));
+        !squeezes && !is_door(loc) && !is_door(loc + delta)
     }
 
     pub fn valid_exit(&self, loc: Point, delta: Point) -> Option<usize> {
         let destination = loc + delta;
         // TODO: Combine conditions
         if self.in_bounds(destination) {
-            if self.can_enter_tile(destination) {
+            if self.is_passable(destination) && self.can_step(loc, delta) {
                 let idx = self.point2d_to_index(destination);
                 Some(idx)
             } else {
@@ -59,9 +209,16 @@ impl Map {
     }
 }
 
+/// A map the size of the screen.
+impl Default for Map {
+    fn default() -> Self {
+        Self::new(SCREEN_WIDTH, SCREEN_HEIGHT)
+    }
+}
+
 impl Algorithm2D for Map {
     fn dimensions(&self) -> Point {
-        Point::new(SCREEN_WIDTH, SCREEN_HEIGHT)
+        Point::new(self.width, self.height)
     }
 
     fn in_bounds(&self, pos: Point) -> bool {
@@ -71,22 +228,14 @@ impl Algorithm2D for Map {
 
 impl BaseMap for Map {
     fn get_available_exits(&self, idx: usize) -> SmallVec<[(usize, f32); 10]> {
-        let mut exits = SmallVec::new();
         let location = self.index_to_point2d(idx);
-
-        if let Some(idx) = self.valid_exit(location, Point::new(-1, 0)) {
-            exits.push((idx, 1.0));
-        }
-        if let Some(idx) = self.valid_exit(location, Point::new(1, 0)) {
-            exits.push((idx, 1.0));
-        }
-        if let Some(idx) = self.valid_exit(location, Point::new(0, -1)) {
-            exits.push((idx, 1.0));
-        }
-        if let Some(idx) = self.valid_exit(location, Point::new(0, 1)) {
-            exits.push((idx, 1.0));
-        }
-        exits
+        self.directions()
+            .iter()
+            .filter_map(|delta| {
+                let idx = self.valid_exit(location, *delta)?;
+                Some((idx, self.path_cost(idx) * step_length(*delta)))
+            })
+            .collect()
     }
 
     fn get_pathing_distance(&self, idx1: usize, idx2: usize) -> f32 {
@@ -94,7 +243,9 @@ impl BaseMap for Map {
     }
 
     fn is_opaque(&self, idx: usize) -> bool {
-        // TODO: Only walls should be opaque?
-        self.tiles[idx] != TileType::Floor
+        matches!(
+            self.tiles[idx],
+            TileType::Wall | TileType::DoorClosed | TileType::DoorSecret
+        )
     }
 }
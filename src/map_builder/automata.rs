@@ -4,16 +4,21 @@ use crate::prelude::*;
 pub struct CellularAutomataArchitect {}
 
 impl CellularAutomataArchitect {
-    fn random_noise_map(&mut self, rng: &mut RandomNumberGenerator, map: &mut Map) {
+    fn random_noise_map(
+        &mut self,
+        rng: &mut RandomNumberGenerator,
+        map: &mut Map,
+        floor_threshold: i32,
+    ) {
         let mut new_tiles = map.tiles.clone();
         new_tiles.iter_mut().enumerate().for_each(|(idx, t)| {
             let pt = map.index_to_point2d(idx);
-            if pt.x == 0 || pt.x == SCREEN_WIDTH - 1 || pt.y == 0 || pt.y == SCREEN_HEIGHT - 1 {
+            if pt.x == 0 || pt.x == map.width - 1 || pt.y == 0 || pt.y == map.height - 1 {
                 // Always make the borders be Walls.
                 *t = TileType::Wall
             } else {
                 let roll = rng.range(0, 100);
-                *t = if roll > 55 {
+                *t = if roll > floor_threshold {
                     TileType::Floor
                 } else {
                     TileType::Wall
@@ -28,7 +33,8 @@ impl CellularAutomataArchitect {
         let mut neighbors = 0;
         for iy in -1..=1 {
             for ix in -1..=1 {
-                if !(ix == 0 && iy == 0) && map.tiles[map_idx(x + ix, y + iy)] == TileType::Wall {
+                if !(ix == 0 && iy == 0) && map.tiles[map.map_idx(x + ix, y + iy)] == TileType::Wall
+                {
                     neighbors += 1;
                 }
             }
@@ -38,10 +44,10 @@ impl CellularAutomataArchitect {
 
     fn iteration(&mut self, map: &mut Map) {
         let mut new_tiles = map.tiles.clone();
-        for y in 1..SCREEN_HEIGHT - 1 {
-            for x in 1..SCREEN_WIDTH - 1 {
+        for y in 1..map.height - 1 {
+            for x in 1..map.width - 1 {
                 let neighbors = self.count_neighbors(x, y, map);
-                let idx = map_idx(x, y);
+                let idx = map.map_idx(x, y);
                 new_tiles[idx] = if neighbors > 4 || neighbors == 0 {
                     TileType::Wall
                 } else {
@@ -53,7 +59,7 @@ impl CellularAutomataArchitect {
     }
 
     fn find_start(&self, map: &Map) -> Point {
-        let center = Point::new(SCREEN_WIDTH / 2, SCREEN_HEIGHT / 2);
+        let center = map.center();
         let closest_point = map
             .tiles
             .iter()
@@ -73,17 +79,18 @@ impl CellularAutomataArchitect {
 }
 
 impl MapArchitect for CellularAutomataArchitect {
-    fn new(&mut self, rng: &mut RandomNumberGenerator) -> MapBuilder {
+    fn new(&mut self, rng: &mut RandomNumberGenerator, config: &GameConfig) -> MapBuilder {
         let mut mb = MapBuilder {
-            map: Map::new(),
+            map: Map::new(config.map_width, config.map_height),
             rooms: Vec::new(),
             monster_spawns: Vec::new(),
             player_start: Point::zero(),
             amulet_start: Point::zero(),
+            prefab: None,
             theme: super::themes::DungeonTheme::new(),
         };
 
-        self.random_noise_map(rng, &mut mb.map);
+        self.random_noise_map(rng, &mut mb.map, config.automata_floor_threshold);
         for _ in 0..10 {
             self.iteration(&mut mb.map)
         }
@@ -91,7 +98,7 @@ impl MapArchitect for CellularAutomataArchitect {
 
         mb.player_start = start;
         mb.amulet_start = mb.find_most_distance();
-        mb.monster_spawns = mb.spawn_monster(rng);
+        mb.monster_spawns = mb.spawn_monster(rng, config);
 
         mb
     }
@@ -106,7 +113,7 @@ mod tests {
         let mut rng = RandomNumberGenerator::new();
 
         let mut architect = CellularAutomataArchitect {};
-        let mb = architect.new(&mut rng);
+        let mb = architect.new(&mut rng, &GameConfig::default());
 
         // Check that both player start and amulet/exit points are floors.
         let player_start_idx = mb.map.point2d_to_index(mb.player_start);
@@ -116,8 +123,8 @@ mod tests {
 
         // Check that there is a path from player to exit.
         let dmap = DijkstraMap::new(
-            SCREEN_WIDTH,
-            SCREEN_HEIGHT,
+            mb.map.width,
+            mb.map.height,
             &[player_start_idx],
             &mb.map,
             1024.0,
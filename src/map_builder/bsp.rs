@@ -0,0 +1,193 @@
+use super::MapArchitect;
+use crate::prelude::*;
+
+/// Leaves are split in two as long as both halves are at least this large.
+const MIN_LEAF_SIZE: i32 = 8;
+/// The smallest room carved in a leaf. Leaves keep a wall around their room, so the rooms
+/// never touch.
+const MIN_ROOM_SIZE: i32 = 3;
+
+pub struct BspArchitect {}
+
+impl BspArchitect {
+    /// Splits `area` in two until its leaves are too small to be split, carves a room in
+    /// each leaf and connects the rooms of both halves. Returns the center of one of the
+    /// rooms inside `area`, for the caller to connect it to its sibling.
+    fn build_leaf(
+        &mut self,
+        rng: &mut RandomNumberGenerator,
+        mb: &mut MapBuilder,
+        area: Rect,
+    ) -> Point {
+        let can_split_vertically = area.width() >= MIN_LEAF_SIZE * 2;
+        let can_split_horizontally = area.height() >= MIN_LEAF_SIZE * 2;
+        let split_vertically = match (can_split_vertically, can_split_horizontally) {
+            (false, false) => return self.carve_room(rng, mb, area),
+            (true, false) => true,
+            (false, true) => false,
+            // Long leaves are split across, so they don't end up as narrow strips.
+            (true, true) if area.width() > area.height() * 5 / 4 => true,
+            (true, true) if area.height() > area.width() * 5 / 4 => false,
+            (true, true) => rng.range(0, 2) == 0,
+        };
+
+        let (first, second) = if split_vertically {
+            let x = rng.range(area.x1 + MIN_LEAF_SIZE, area.x2 - MIN_LEAF_SIZE + 1);
+            (
+                Rect::with_exact(area.x1, area.y1, x, area.y2),
+                Rect::with_exact(x, area.y1, area.x2, area.y2),
+            )
+        } else {
+            let y = rng.range(area.y1 + MIN_LEAF_SIZE, area.y2 - MIN_LEAF_SIZE + 1);
+            (
+                Rect::with_exact(area.x1, area.y1, area.x2, y),
+                Rect::with_exact(area.x1, y, area.x2, area.y2),
+            )
+        };
+        let first = self.build_leaf(rng, mb, first);
+        let second = self.build_leaf(rng, mb, second);
+        self.connect(rng, mb, first, second);
+        if rng.range(0, 2) == 0 {
+            first
+        } else {
+            second
+        }
+    }
+
+    fn carve_room(
+        &mut self,
+        rng: &mut RandomNumberGenerator,
+        mb: &mut MapBuilder,
+        leaf: Rect,
+    ) -> Point {
+        let width = room_length(rng, leaf.width());
+        let height = room_length(rng, leaf.height());
+        let room = Rect::with_size(
+            room_start(rng, leaf.x1, leaf.width(), width),
+            room_start(rng, leaf.y1, leaf.height(), height),
+            width,
+            height,
+        );
+        room.for_each(|p| {
+            if let Some(idx) = mb.map.try_idx(p) {
+                mb.map.tiles[idx] = TileType::Floor;
+            }
+        });
+        mb.rooms.push(room);
+        room.center()
+    }
+
+    /// Digs an L shaped corridor between `from` and `to`.
+    fn connect(
+        &mut self,
+        rng: &mut RandomNumberGenerator,
+        mb: &mut MapBuilder,
+        from: Point,
+        to: Point,
+    ) {
+        let corner = if rng.range(0, 2) == 0 {
+            Point::new(to.x, from.y)
+        } else {
+            Point::new(from.x, to.y)
+        };
+        for (start, end) in [(from, corner), (corner, to)] {
+            for y in start.y.min(end.y)..=start.y.max(end.y) {
+                for x in start.x.min(end.x)..=start.x.max(end.x) {
+                    if let Some(idx) = mb.map.try_idx(Point::new(x, y)) {
+                        mb.map.tiles[idx] = TileType::Floor;
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// How wide a room is across a leaf `size` tiles wide. Leaves too narrow for a room of
+/// `MIN_ROOM_SIZE` and a wall on both sides get as wide a room as fits in them.
+fn room_length(rng: &mut RandomNumberGenerator, size: i32) -> i32 {
+    if size - 2 < MIN_ROOM_SIZE {
+        (size - 2).max(1)
+    } else {
+        rng.range(MIN_ROOM_SIZE, size - 1)
+    }
+}
+
+/// Where a room `length` tiles wide starts across a leaf starting at `start` and `size`
+/// tiles wide. Rooms that fill their narrow leaf are centred in it.
+fn room_start(rng: &mut RandomNumberGenerator, start: i32, size: i32, length: i32) -> i32 {
+    if size - length < 2 {
+        start + (size - length).max(0) / 2
+    } else {
+        rng.range(start + 1, start + size - length)
+    }
+}
+
+impl MapArchitect for BspArchitect {
+    fn new(&mut self, rng: &mut RandomNumberGenerator, config: &GameConfig) -> MapBuilder {
+        let mut mb = MapBuilder {
+            map: Map::new(config.map_width, config.map_height),
+            rooms: Vec::new(),
+            monster_spawns: Vec::new(),
+            player_start: Point::zero(),
+            amulet_start: Point::zero(),
+            prefab: None,
+            theme: super::themes::DungeonTheme::new(),
+        };
+
+        mb.fill(TileType::Wall);
+        // The leaves stay inside the edge of the map, so it stays walled.
+        let area = Rect::with_exact(1, 1, mb.map.width - 1, mb.map.height - 1);
+        self.build_leaf(rng, &mut mb, area);
+        mb.player_start = mb.rooms[0].center();
+        mb.amulet_start = mb.find_most_distance();
+        for room in mb.rooms.iter().skip(1) {
+            mb.monster_spawns.push(room.center());
+        }
+        mb
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rooms_are_apart_and_all_connected() {
+        for seed in 0..10 {
+            let mut rng = RandomNumberGenerator::seeded(seed);
+            let mb = BspArchitect {}.new(&mut rng, &GameConfig::default());
+            assert!(mb.rooms.len() > 1);
+            for (i, room) in mb.rooms.iter().enumerate() {
+                assert!(mb
+                    .rooms
+                    .iter()
+                    .skip(i + 1)
+                    .all(|other| !room.intersect(other)));
+            }
+
+            let dijkstra_map = mb.build_player_distance_map();
+            assert!(mb.rooms.iter().all(|room| {
+                dijkstra_map.map[mb.map.point2d_to_index(room.center())] < f32::MAX
+            }));
+            assert!((0..mb.map.width).all(|x| {
+                mb.map.tiles[mb.map.map_idx(x, 0)] == TileType::Wall
+                    && mb.map.tiles[mb.map.map_idx(x, mb.map.height - 1)] == TileType::Wall
+            }));
+        }
+    }
+
+    #[test]
+    fn narrow_leaves_still_get_a_room() {
+        let mut rng = RandomNumberGenerator::seeded(1);
+        let mut mb = BspArchitect {}.new(&mut rng, &GameConfig::default());
+        for size in 1..6 {
+            let leaf = Rect::with_size(2, 2, size, 8 - size);
+            let center = BspArchitect {}.carve_room(&mut rng, &mut mb, leaf);
+            assert!(leaf.point_in_rect(center));
+            assert_eq!(
+                mb.map.tiles[mb.map.point2d_to_index(center)],
+                TileType::Floor
+            );
+        }
+    }
+}
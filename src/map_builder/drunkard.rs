@@ -1,14 +1,16 @@
 use super::MapArchitect;
 use crate::prelude::*;
 
-const STAGGER_DISTANCE: usize = 400;
-const NUM_TILES: usize = (SCREEN_WIDTH * SCREEN_HEIGHT) as usize;
-const DESIRED_FLOOR: usize = NUM_TILES / 3;
-
 pub struct DrunkardWalkArchitect {}
 
 impl DrunkardWalkArchitect {
-    fn drunkard(&mut self, start: &Point, rng: &mut RandomNumberGenerator, map: &mut Map) {
+    fn drunkard(
+        &mut self,
+        start: &Point,
+        rng: &mut RandomNumberGenerator,
+        map: &mut Map,
+        stagger_distance: usize,
+    ) {
         let mut drunkard_pos = *start;
         let mut distance_staggered = 0;
 
@@ -17,9 +19,9 @@ impl DrunkardWalkArchitect {
             // Break if arrived in a border (or is fully out of bounds)
             if !map.in_bounds(drunkard_pos)
                 || drunkard_pos.x == 0
-                || drunkard_pos.x == SCREEN_WIDTH - 1
+                || drunkard_pos.x == map.width - 1
                 || drunkard_pos.y == 0
-                || drunkard_pos.y == SCREEN_HEIGHT - 1
+                || drunkard_pos.y == map.height - 1
             {
                 break;
             }
@@ -33,7 +35,7 @@ impl DrunkardWalkArchitect {
             }
 
             distance_staggered += 1;
-            if distance_staggered > STAGGER_DISTANCE {
+            if distance_staggered > stagger_distance {
                 break;
             }
         }
@@ -41,19 +43,22 @@ impl DrunkardWalkArchitect {
 }
 
 impl MapArchitect for DrunkardWalkArchitect {
-    fn new(&mut self, rng: &mut RandomNumberGenerator) -> MapBuilder {
+    fn new(&mut self, rng: &mut RandomNumberGenerator, config: &GameConfig) -> MapBuilder {
         let mut mb = MapBuilder {
-            map: Map::new(),
+            map: Map::new(config.map_width, config.map_height),
             rooms: Vec::new(),
             monster_spawns: Vec::new(),
             player_start: Point::zero(),
             amulet_start: Point::zero(),
+            prefab: None,
             theme: super::themes::DungeonTheme::new(),
         };
 
         mb.fill(TileType::Wall);
-        let center = Point::new(SCREEN_WIDTH / 2, SCREEN_HEIGHT / 2);
-        self.drunkard(&center, rng, &mut mb.map);
+        let center = mb.map.center();
+        self.drunkard(&center, rng, &mut mb.map, config.stagger_distance);
+
+        let desired_floor = (mb.map.tiles.len() as f32 * config.desired_floor) as usize;
 
         while mb
             .map
@@ -61,20 +66,21 @@ impl MapArchitect for DrunkardWalkArchitect {
             .iter()
             .filter(|t| **t == TileType::Floor)
             .count()
-            < DESIRED_FLOOR
+            < desired_floor
         {
             self.drunkard(
                 &Point::new(
-                    rng.range(1, SCREEN_WIDTH - 1),
-                    rng.range(1, SCREEN_HEIGHT - 1),
+                    rng.range(1, mb.map.width - 1),
+                    rng.range(1, mb.map.height - 1),
                 ),
                 rng,
                 &mut mb.map,
+                config.stagger_distance,
             );
 
             let dijkstra_map = DijkstraMap::new(
-                SCREEN_WIDTH,
-                SCREEN_HEIGHT,
+                mb.map.width,
+                mb.map.height,
                 &[mb.map.point2d_to_index(center)],
                 &mb.map,
                 1024.0,
@@ -89,7 +95,7 @@ impl MapArchitect for DrunkardWalkArchitect {
 
         mb.player_start = center;
         mb.amulet_start = mb.find_most_distance();
-        mb.monster_spawns = mb.spawn_monster(rng);
+        mb.monster_spawns = mb.spawn_monster(rng, config);
 
         mb
     }
@@ -104,7 +110,7 @@ mod tests {
         let mut rng = RandomNumberGenerator::new();
 
         let mut architect = DrunkardWalkArchitect {};
-        let mb = architect.new(&mut rng);
+        let mb = architect.new(&mut rng, &GameConfig::default());
 
         // Check that both player start and amulet/exit points are floors.
         let player_start_idx = mb.map.point2d_to_index(mb.player_start);
@@ -114,8 +120,8 @@ mod tests {
 
         // Check that there is a path from player to exit.
         let dmap = DijkstraMap::new(
-            SCREEN_WIDTH,
-            SCREEN_HEIGHT,
+            mb.map.width,
+            mb.map.height,
             &[player_start_idx],
             &mb.map,
             1024.0,
@@ -4,23 +4,24 @@ use crate::prelude::*;
 pub struct EmptyArchitect {}
 
 impl MapArchitect for EmptyArchitect {
-    fn new(&mut self, rng: &mut RandomNumberGenerator) -> MapBuilder {
+    fn new(&mut self, rng: &mut RandomNumberGenerator, config: &GameConfig) -> MapBuilder {
         let mut mb = MapBuilder {
-            map: Map::new(),
+            map: Map::new(config.map_width, config.map_height),
             rooms: Vec::new(),
             monster_spawns: Vec::new(),
             player_start: Point::zero(),
             amulet_start: Point::zero(),
+            prefab: None,
             theme: super::themes::DungeonTheme::new(),
         };
 
         mb.fill(TileType::Floor);
-        mb.player_start = Point::new(SCREEN_WIDTH / 2, SCREEN_HEIGHT / 2);
+        mb.player_start = mb.map.center();
         mb.amulet_start = mb.find_most_distance();
-        for _ in 0..50 {
+        for _ in 0..config.num_monsters {
             mb.monster_spawns.push(Point::new(
-                rng.range(1, SCREEN_WIDTH),
-                rng.range(1, SCREEN_HEIGHT),
+                rng.range(1, mb.map.width),
+                rng.range(1, mb.map.height),
             ));
         }
         mb
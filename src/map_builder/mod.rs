@@ -1,26 +1,61 @@
 mod automata;
+mod bsp;
 mod drunkard;
 mod empty;
 mod prefab;
 mod rooms;
+mod terrain;
 mod themes;
 
 use crate::prelude::*;
 use automata::CellularAutomataArchitect;
+use bsp::BspArchitect;
 use drunkard::DrunkardWalkArchitect;
+use empty::EmptyArchitect;
 use rooms::RoomsArchitect;
+use std::str::FromStr;
 
-use self::{
-    prefab::apply_prefab,
-    themes::{DungeonTheme, ForestTheme},
-};
+use self::themes::{DungeonTheme, ForestTheme};
+pub use themes::ThemeKind;
 
 pub trait MapTheme: Sync + Send {
+    fn kind(&self) -> ThemeKind;
     fn tile_to_render(&self, tile_type: TileType) -> FontCharType;
+    /// The light reaching every tile of the level, before any light source.
+    fn ambient_light(&self) -> RGB;
 }
 
+#[allow(clippy::new_ret_no_self, clippy::wrong_self_convention)]
 trait MapArchitect {
-    fn new(&mut self, rng: &mut RandomNumberGenerator) -> MapBuilder;
+    fn new(&mut self, rng: &mut RandomNumberGenerator, config: &GameConfig) -> MapBuilder;
+}
+
+/// The available map architects, so one can be picked explicitly instead of randomly.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ArchitectKind {
+    DrunkardWalk,
+    Rooms,
+    CellularAutomata,
+    Bsp,
+    Empty,
+}
+
+impl FromStr for ArchitectKind {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "drunkard" => Ok(ArchitectKind::DrunkardWalk),
+            "rooms" => Ok(ArchitectKind::Rooms),
+            "automata" => Ok(ArchitectKind::CellularAutomata),
+            "bsp" => Ok(ArchitectKind::Bsp),
+            "empty" => Ok(ArchitectKind::Empty),
+            _ => Err(format!(
+                "Unknown architect '{}', expected one of: drunkard, rooms, automata, bsp, empty",
+                name
+            )),
+        }
+    }
 }
 
 pub struct MapBuilder {
@@ -29,25 +64,83 @@ pub struct MapBuilder {
     pub monster_spawns: Vec<Point>,
     pub player_start: Point,
     pub amulet_start: Point,
+    pub prefab: Option<Rect>,
     pub theme: Box<dyn MapTheme>,
 }
 
 impl MapBuilder {
-    pub fn new(rng: &mut RandomNumberGenerator) -> Self {
-        let mut architect: Box<dyn MapArchitect> = match rng.range(0, 3) {
-            0 => Box::new(DrunkardWalkArchitect {}),
-            1 => Box::new(RoomsArchitect {}),
-            _ => Box::new(CellularAutomataArchitect {}),
+    pub fn new(rng: &mut RandomNumberGenerator, config: &GameConfig) -> Self {
+        let architect = match rng.range(0, 4) {
+            0 => ArchitectKind::DrunkardWalk,
+            1 => ArchitectKind::Rooms,
+            2 => ArchitectKind::Bsp,
+            _ => ArchitectKind::CellularAutomata,
         };
-        let mut mb = architect.new(rng);
-        apply_prefab(&mut mb, rng);
+        Self::with_architect(architect, rng, config)
+    }
+
+    pub fn with_architect(
+        kind: ArchitectKind,
+        rng: &mut RandomNumberGenerator,
+        config: &GameConfig,
+    ) -> Self {
+        let mut architect: Box<dyn MapArchitect> = match kind {
+            ArchitectKind::DrunkardWalk => Box::new(DrunkardWalkArchitect {}),
+            ArchitectKind::Rooms => Box::new(RoomsArchitect {}),
+            ArchitectKind::CellularAutomata => Box::new(CellularAutomataArchitect {}),
+            ArchitectKind::Bsp => Box::new(BspArchitect {}),
+            ArchitectKind::Empty => Box::new(EmptyArchitect {}),
+        };
+        let mut mb = architect.new(rng, config);
+        mb.map.diagonal_movement = config.diagonal_movement;
+        mb
+    }
 
-        mb.theme = match rng.range(0, 2) {
+    /// Stamps the fortress vault somewhere on the map, if it fits.
+    pub fn apply_prefab(&mut self, rng: &mut RandomNumberGenerator) {
+        prefab::apply_prefab(self, rng);
+    }
+
+    /// Adds water, lava and rubble to the floor of the level at `depth`.
+    pub fn place_terrain(&mut self, rng: &mut RandomNumberGenerator, depth: u32) {
+        terrain::place_terrain(self, rng, depth);
+    }
+
+    /// Picks the look of the level at random.
+    pub fn pick_theme(&mut self, rng: &mut RandomNumberGenerator) {
+        self.theme = match rng.range(0, 2) {
             0 => DungeonTheme::new(),
             _ => ForestTheme::new(),
         };
+    }
 
-        mb
+    /// Renders the generated map as ASCII, one line per map row. Besides the tiles it marks
+    /// the player start (`@`), the amulet/exit (`A`) and the monster spawns (`M`), while the
+    /// tiles of a placed prefab are drawn as `%` (walls) and `,` (floors).
+    pub fn to_ascii(&self) -> String {
+        let mut output = String::new();
+        for y in 0..self.map.height {
+            for x in 0..self.map.width {
+                let pt = Point::new(x, y);
+                let in_prefab = self.prefab.is_some_and(|r| r.point_in_rect(pt));
+                let c = if pt == self.player_start {
+                    '@'
+                } else if pt == self.amulet_start {
+                    'A'
+                } else if self.monster_spawns.contains(&pt) {
+                    'M'
+                } else {
+                    match (self.map.tiles[self.map.map_idx(x, y)], in_prefab) {
+                        (TileType::Wall, true) => '%',
+                        (TileType::Floor, true) => ',',
+                        (tile, _) => tile.to_ascii(),
+                    }
+                };
+                output.push(c);
+            }
+            output.push('\n');
+        }
+        output
     }
 
     fn fill(&mut self, tile: TileType) {
@@ -70,10 +163,27 @@ impl MapBuilder {
         )
     }
 
+    /// The reachable floor tile farthest from the player start, other than the `taken` ones.
+    pub fn find_most_distant_floor(&self, taken: &[Point]) -> Option<Point> {
+        const UNREACHABLE: f32 = f32::MAX;
+        let dijkstra_map = self.build_player_distance_map();
+        dijkstra_map
+            .map
+            .iter()
+            .enumerate()
+            .filter(|(idx, dist)| {
+                **dist < UNREACHABLE
+                    && self.map.tiles[*idx] == TileType::Floor
+                    && !taken.contains(&self.map.index_to_point2d(*idx))
+            })
+            .max_by(|a, b| a.1.partial_cmp(b.1).unwrap())
+            .map(|(idx, _)| self.map.index_to_point2d(idx))
+    }
+
     fn build_player_distance_map(&self) -> DijkstraMap {
         DijkstraMap::new(
-            SCREEN_WIDTH,
-            SCREEN_HEIGHT,
+            self.map.width,
+            self.map.height,
             &[self.map.point2d_to_index(self.player_start)],
             &self.map,
             1024.0,
@@ -81,8 +191,29 @@ impl MapBuilder {
     }
 
     /// Should be called only after self.player_start is set.
-    fn spawn_monster(&self, rng: &mut RandomNumberGenerator) -> Vec<Point> {
-        const NUM_MONSTERS: usize = 50;
+    fn spawn_monster(&self, rng: &mut RandomNumberGenerator, config: &GameConfig) -> Vec<Point> {
+        // Not too close to the start position.
+        self.random_floor_tiles(rng, config.num_monsters, 10.0, &[])
+    }
+
+    /// Picks up to `count` floor tiles for the traps, out of the way of the monster spawns
+    /// and the points of interest. Should be called once the terrain is placed.
+    pub fn trap_spawns(&self, rng: &mut RandomNumberGenerator, count: usize) -> Vec<Point> {
+        let mut excluded = self.monster_spawns.clone();
+        excluded.push(self.amulet_start);
+        // The player doesn't walk into a trap on the first step of a level.
+        self.random_floor_tiles(rng, count, 2.0, &excluded)
+    }
+
+    /// Picks up to `count` distinct floor tiles that are reachable by the player, at least
+    /// `min_distance` steps away from the start position and not in `excluded`.
+    fn random_floor_tiles(
+        &self,
+        rng: &mut RandomNumberGenerator,
+        count: usize,
+        min_distance: f32,
+        excluded: &[Point],
+    ) -> Vec<Point> {
         const UNREACHABLE: f32 = f32::MAX;
         let dijkstra_map = self.build_player_distance_map();
         let mut spawnable_tiles: Vec<Point> = self
@@ -90,23 +221,104 @@ impl MapBuilder {
             .tiles
             .iter()
             .enumerate()
-            // Only spawn in Floors and in places that are reachable by the player
-            // but not too close to the start position.
             .filter(|(idx, t)| {
                 **t == TileType::Floor
-                    && dijkstra_map.map[*idx] >= 10.0
+                    && dijkstra_map.map[*idx] >= min_distance
                     && dijkstra_map.map[*idx] < UNREACHABLE
             })
             .map(|(idx, _)| self.map.index_to_point2d(idx))
+            .filter(|pt| !excluded.contains(pt))
             .collect();
 
-        let mut spawns = Vec::new();
-        // TODO: We could shuffle spawnable_tiles and grab the first NUM_MONSTERS entries.
-        for _ in 0..NUM_MONSTERS {
-            let target_index = rng.random_slice_index(&spawnable_tiles).unwrap();
-            spawns.push(spawnable_tiles[target_index]);
-            spawnable_tiles.remove(target_index);
+        // TODO: We could shuffle spawnable_tiles and grab the first `count` entries.
+        (0..count)
+            .take(spawnable_tiles.len())
+            .filter_map(|_| {
+                let target_index = rng.random_slice_index(&spawnable_tiles)?;
+                Some(spawnable_tiles.remove(target_index))
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn same_seed_builds_same_map() {
+        let mb1 = MapBuilder::new(
+            &mut RandomNumberGenerator::seeded(42),
+            &GameConfig::default(),
+        );
+        let mb2 = MapBuilder::new(
+            &mut RandomNumberGenerator::seeded(42),
+            &GameConfig::default(),
+        );
+
+        assert_eq!(mb1.map.tiles, mb2.map.tiles);
+        assert_eq!(mb1.player_start, mb2.player_start);
+        assert_eq!(mb1.amulet_start, mb2.amulet_start);
+        assert_eq!(mb1.monster_spawns, mb2.monster_spawns);
+    }
+
+    #[test]
+    fn ascii_dump_marks_points_of_interest() {
+        let mut rng = RandomNumberGenerator::seeded(42);
+        let mb = MapBuilder::with_architect(ArchitectKind::Empty, &mut rng, &GameConfig::default());
+        let ascii = mb.to_ascii();
+
+        let lines: Vec<&str> = ascii.lines().collect();
+        assert_eq!(lines.len(), mb.map.height as usize);
+        assert!(lines.iter().all(|l| l.len() == mb.map.width as usize));
+
+        let at = |pt: Point| lines[pt.y as usize].chars().nth(pt.x as usize).unwrap();
+        assert_eq!(at(mb.player_start), '@');
+        assert_eq!(at(mb.amulet_start), 'A');
+    }
+
+    #[test]
+    fn architects_follow_the_configured_map_size() {
+        for (width, height) in [(40, 30), (120, 90)] {
+            let config = GameConfig {
+                map_width: width,
+                map_height: height,
+                ..Default::default()
+            };
+            for kind in [
+                ArchitectKind::DrunkardWalk,
+                ArchitectKind::Rooms,
+                ArchitectKind::CellularAutomata,
+                ArchitectKind::Bsp,
+                ArchitectKind::Empty,
+            ] {
+                let mut rng = RandomNumberGenerator::seeded(7);
+                let mb = MapBuilder::with_architect(kind, &mut rng, &config);
+                assert_eq!(mb.map.tiles.len(), (width * height) as usize);
+                assert!(mb.map.in_bounds(mb.player_start));
+                assert!(mb.map.in_bounds(mb.amulet_start));
+                assert!(mb.monster_spawns.iter().all(|pt| mb.map.in_bounds(*pt)));
+            }
         }
-        spawns
+    }
+
+    #[test]
+    fn more_monsters_than_floor_tiles_fill_the_floor() {
+        let config = GameConfig {
+            num_monsters: 100_000,
+            ..Default::default()
+        };
+        let mut rng = RandomNumberGenerator::seeded(3);
+        let mb = MapBuilder::with_architect(ArchitectKind::DrunkardWalk, &mut rng, &config);
+        assert!(!mb.monster_spawns.is_empty());
+        assert!(mb.monster_spawns.len() < mb.map.tiles.len());
+    }
+
+    #[test]
+    fn parse_architect_names() {
+        assert_eq!("rooms".parse(), Ok(ArchitectKind::Rooms));
+        assert_eq!("empty".parse(), Ok(ArchitectKind::Empty));
+        assert_eq!("bsp".parse(), Ok(ArchitectKind::Bsp));
+        assert!("maze".parse::<ArchitectKind>().is_err());
     }
 }
@@ -3,7 +3,7 @@ use crate::prelude::*;
 const FORTRESS: (&str, i32, i32) = (
     "
 ------------
----######---
+---##S###---
 ---#----#---
 ---#-M--#---
 -###----###-
@@ -19,11 +19,14 @@ const FORTRESS: (&str, i32, i32) = (
 );
 
 pub fn apply_prefab(mb: &mut MapBuilder, rng: &mut RandomNumberGenerator) {
+    if mb.map.width <= FORTRESS.1 || mb.map.height <= FORTRESS.2 {
+        return;
+    }
     let mut placement = None;
 
     let dijkstra_map = DijkstraMap::new(
-        SCREEN_WIDTH,
-        SCREEN_HEIGHT,
+        mb.map.width,
+        mb.map.height,
         &[mb.map.point2d_to_index(mb.player_start)],
         &mb.map,
         1024.0,
@@ -32,8 +35,8 @@ pub fn apply_prefab(mb: &mut MapBuilder, rng: &mut RandomNumberGenerator) {
     let mut attempts = 0;
     while placement.is_none() && attempts < 10 {
         let dimensions = Rect::with_size(
-            rng.range(0, SCREEN_WIDTH - FORTRESS.1),
-            rng.range(0, SCREEN_HEIGHT - FORTRESS.2),
+            rng.range(0, mb.map.width - FORTRESS.1),
+            rng.range(0, mb.map.height - FORTRESS.2),
             FORTRESS.1,
             FORTRESS.2,
         );
@@ -53,6 +56,7 @@ pub fn apply_prefab(mb: &mut MapBuilder, rng: &mut RandomNumberGenerator) {
 
         if can_place {
             placement = Some(Point::new(dimensions.x1, dimensions.y1));
+            mb.prefab = Some(dimensions);
             let points = dimensions.point_set();
             mb.monster_spawns.retain(|pt| !points.contains(pt));
         }
@@ -69,7 +73,7 @@ pub fn apply_prefab(mb: &mut MapBuilder, rng: &mut RandomNumberGenerator) {
         let mut i = 0;
         for ty in placement.y..placement.y + FORTRESS.2 {
             for tx in placement.x..placement.x + FORTRESS.1 {
-                let idx = map_idx(tx, ty);
+                let idx = mb.map.map_idx(tx, ty);
                 let c = string_vec[i];
                 match c {
                     'M' => {
@@ -78,6 +82,7 @@ pub fn apply_prefab(mb: &mut MapBuilder, rng: &mut RandomNumberGenerator) {
                     }
                     '-' => mb.map.tiles[idx] = TileType::Floor,
                     '#' => mb.map.tiles[idx] = TileType::Wall,
+                    'S' => mb.map.tiles[idx] = TileType::DoorSecret,
                     _ => println!("No idea what to do with [{}]", c),
                 }
                 i += 1;
@@ -1,16 +1,27 @@
 use super::MapArchitect;
 use crate::prelude::*;
 
-const NUM_ROOMS: usize = 20;
+/// The chance, out of 100, that a door is a secret one.
+const SECRET_DOOR_CHANCE: i32 = 15;
 
 pub struct RoomsArchitect {}
 
 impl RoomsArchitect {
-    fn build_random_rooms(&mut self, rng: &mut RandomNumberGenerator, mb: &mut MapBuilder) {
-        while mb.rooms.len() < NUM_ROOMS {
+    fn build_random_rooms(
+        &mut self,
+        rng: &mut RandomNumberGenerator,
+        mb: &mut MapBuilder,
+        num_rooms: usize,
+    ) {
+        // Small maps may not fit all the rooms, so give up after a while.
+        // The random rooms need a map at least 12 tiles wide and tall.
+        let fits = mb.map.width > 11 && mb.map.height > 11;
+        let mut attempts = 0;
+        while fits && mb.rooms.len() < num_rooms && attempts < num_rooms * 100 {
+            attempts += 1;
             let room = Rect::with_size(
-                rng.range(1, SCREEN_WIDTH - 10),
-                rng.range(1, SCREEN_HEIGHT - 10),
+                rng.range(1, mb.map.width - 10),
+                rng.range(1, mb.map.height - 10),
                 rng.range(2, 10),
                 rng.range(2, 10),
             );
@@ -22,23 +33,32 @@ impl RoomsArchitect {
                 }
             }
             if !overlap {
-                room.for_each(|p| {
-                    // TODO: Use self.map.try_idx(p)?
-                    if p.x > 0 && p.x < SCREEN_WIDTH && p.y > 0 && p.y < SCREEN_HEIGHT {
-                        let idx = map_idx(p.x, p.y);
-                        mb.map.tiles[idx] = TileType::Floor;
-                    }
-                });
-                mb.rooms.push(room);
+                self.carve_room(mb, room);
             }
         }
+        // Should none fit, the level is a single room in the middle.
+        if mb.rooms.is_empty() {
+            let center = mb.map.center();
+            self.carve_room(mb, Rect::with_size(center.x - 1, center.y - 1, 3, 3));
+        }
+    }
+
+    fn carve_room(&mut self, mb: &mut MapBuilder, room: Rect) {
+        room.for_each(|p| {
+            if p.x > 0 && p.y > 0 {
+                if let Some(idx) = mb.map.try_idx(p) {
+                    mb.map.tiles[idx] = TileType::Floor;
+                }
+            }
+        });
+        mb.rooms.push(room);
     }
 
     fn apply_vertical_tunnel(&mut self, y1: i32, y2: i32, x: i32, mb: &mut MapBuilder) {
         use std::cmp::{max, min};
         for y in min(y1, y2)..=max(y1, y2) {
             if let Some(idx) = mb.map.try_idx(Point::new(x, y)) {
-                mb.map.tiles[idx as usize] = TileType::Floor;
+                mb.map.tiles[idx] = TileType::Floor;
             }
         }
     }
@@ -47,14 +67,14 @@ impl RoomsArchitect {
         use std::cmp::{max, min};
         for x in min(x1, x2)..=max(x1, x2) {
             if let Some(idx) = mb.map.try_idx(Point::new(x, y)) {
-                mb.map.tiles[idx as usize] = TileType::Floor;
+                mb.map.tiles[idx] = TileType::Floor;
             }
         }
     }
 
     fn build_corridors(&mut self, rng: &mut RandomNumberGenerator, mb: &mut MapBuilder) {
         let mut rooms = mb.rooms.clone();
-        rooms.sort_by(|a, b| a.center().x.cmp(&b.center().x));
+        rooms.sort_by_key(|r| r.center().x);
 
         for (i, room) in rooms.iter().enumerate().skip(1) {
             let prev = rooms[i - 1].center();
@@ -69,24 +89,79 @@ impl RoomsArchitect {
             }
         }
     }
+
+    /// Puts a closed door where a corridor enters a room: on a floor tile right outside
+    /// the room that has walls on both sides, so it is a one tile wide doorway. Some of them
+    /// are secret doors, as long as the amulet can still be reached without finding them.
+    fn place_doors(&mut self, rng: &mut RandomNumberGenerator, mb: &mut MapBuilder) {
+        let mut doors = Vec::new();
+        for room in mb.rooms.iter() {
+            let horizontal_sides = (room.x1..room.x2)
+                .flat_map(|x| [Point::new(x, room.y1 - 1), Point::new(x, room.y2)])
+                .map(|pt| (pt, Point::new(1, 0)));
+            let vertical_sides = (room.y1..room.y2)
+                .flat_map(|y| [Point::new(room.x1 - 1, y), Point::new(room.x2, y)])
+                .map(|pt| (pt, Point::new(0, 1)));
+            horizontal_sides
+                .chain(vertical_sides)
+                .filter(|(pt, along)| {
+                    let is = |pt: Point, tile| {
+                        mb.map.try_idx(pt).map(|idx| mb.map.tiles[idx]) == Some(tile)
+                    };
+                    is(*pt, TileType::Floor)
+                        && is(*pt + *along, TileType::Wall)
+                        && is(*pt - *along, TileType::Wall)
+                        && *pt != mb.player_start
+                        && *pt != mb.amulet_start
+                })
+                .for_each(|(pt, _)| doors.push(pt));
+        }
+        doors.iter().for_each(|pt| {
+            let idx = mb.map.point2d_to_index(*pt);
+            mb.map.tiles[idx] = TileType::DoorClosed;
+        });
+        doors.iter().for_each(|pt| {
+            if rng.range(0, 100) >= SECRET_DOOR_CHANCE {
+                return;
+            }
+            let idx = mb.map.point2d_to_index(*pt);
+            mb.map.tiles[idx] = TileType::DoorSecret;
+            if !amulet_reachable(mb) {
+                mb.map.tiles[idx] = TileType::DoorClosed;
+            }
+        });
+    }
+}
+
+fn amulet_reachable(mb: &MapBuilder) -> bool {
+    let dijkstra_map = DijkstraMap::new(
+        mb.map.width,
+        mb.map.height,
+        &[mb.map.point2d_to_index(mb.player_start)],
+        &mb.map,
+        1024.0,
+    );
+    dijkstra_map.map[mb.map.point2d_to_index(mb.amulet_start)] < f32::MAX
 }
 
 impl MapArchitect for RoomsArchitect {
-    fn new(&mut self, rng: &mut RandomNumberGenerator) -> MapBuilder {
+    fn new(&mut self, rng: &mut RandomNumberGenerator, config: &GameConfig) -> MapBuilder {
         let mut mb = MapBuilder {
-            map: Map::new(),
+            map: Map::new(config.map_width, config.map_height),
             rooms: Vec::new(),
             monster_spawns: Vec::new(),
             player_start: Point::zero(),
             amulet_start: Point::zero(),
+            prefab: None,
             theme: super::themes::DungeonTheme::new(),
         };
 
         mb.fill(TileType::Wall);
-        self.build_random_rooms(rng, &mut mb);
+        self.build_random_rooms(rng, &mut mb, config.num_rooms);
         self.build_corridors(rng, &mut mb);
         mb.player_start = mb.rooms[0].center();
         mb.amulet_start = mb.find_most_distance();
+        self.place_doors(rng, &mut mb);
         for room in mb.rooms.iter().skip(1) {
             mb.monster_spawns.push(room.center());
         }
@@ -103,7 +178,7 @@ mod tests {
         let mut rng = RandomNumberGenerator::new();
 
         let mut architect = RoomsArchitect {};
-        let mb = architect.new(&mut rng);
+        let mb = architect.new(&mut rng, &GameConfig::default());
 
         // Check that both player start and amulet/exit points are floors.
         let player_start_idx = mb.map.point2d_to_index(mb.player_start);
@@ -113,12 +188,58 @@ mod tests {
 
         // Check that there is a path from player to exit.
         let dmap = DijkstraMap::new(
-            SCREEN_WIDTH,
-            SCREEN_HEIGHT,
+            mb.map.width,
+            mb.map.height,
             &[player_start_idx],
             &mb.map,
             1024.0,
         );
         assert_ne!(dmap.map[exit_idx], f32::MAX);
     }
+
+    #[test]
+    fn tiny_maps_still_get_a_room() {
+        let config = GameConfig {
+            map_width: 8,
+            map_height: 8,
+            ..Default::default()
+        };
+        let mut rng = RandomNumberGenerator::seeded(1);
+        let mb = RoomsArchitect {}.new(&mut rng, &config);
+        assert_eq!(mb.rooms.len(), 1);
+        let player_start_idx = mb.map.point2d_to_index(mb.player_start);
+        assert_eq!(mb.map.tiles[player_start_idx], TileType::Floor);
+    }
+
+    #[test]
+    fn doors_close_one_tile_wide_doorways() {
+        let mut rng = RandomNumberGenerator::seeded(3);
+        let mb = RoomsArchitect {}.new(&mut rng, &GameConfig::default());
+        let doors: Vec<Point> = (0..mb.map.tiles.len())
+            .filter(|idx| mb.map.tiles[*idx] == TileType::DoorClosed)
+            .map(|idx| mb.map.index_to_point2d(idx))
+            .collect();
+        assert!(!doors.is_empty());
+
+        let is_wall = |pt: Point| mb.map.tiles[mb.map.point2d_to_index(pt)] == TileType::Wall;
+        for door in doors {
+            let walled_sideways =
+                is_wall(door + Point::new(1, 0)) && is_wall(door - Point::new(1, 0));
+            let walled_vertically =
+                is_wall(door + Point::new(0, 1)) && is_wall(door - Point::new(0, 1));
+            assert!(walled_sideways || walled_vertically);
+        }
+    }
+
+    #[test]
+    fn secret_doors_never_hide_the_amulet() {
+        let mut found_secret_door = false;
+        for seed in 0..20 {
+            let mut rng = RandomNumberGenerator::seeded(seed);
+            let mb = RoomsArchitect {}.new(&mut rng, &GameConfig::default());
+            found_secret_door |= mb.map.tiles.contains(&TileType::DoorSecret);
+            assert!(amulet_reachable(&mb));
+        }
+        assert!(found_secret_door);
+    }
 }
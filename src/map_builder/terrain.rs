@@ -0,0 +1,188 @@
+use crate::prelude::*;
+use std::collections::HashSet;
+
+const NOISE_FREQUENCY: f32 = 0.08;
+const SHALLOW_WATER_LEVEL: f32 = 0.4;
+const DEEP_WATER_LEVEL: f32 = 0.55;
+const LAVA_LEVEL: f32 = 0.5;
+/// The percentage of the floor tiles along the walls that become rubble.
+const RUBBLE_CHANCE: i32 = 4;
+
+/// Floods parts of the floor with pools of water and, below the first level, lava, and
+/// scatters rubble along the walls. The points of interest of the level are left as they
+/// are, and so are the tiles of the prefab.
+pub fn place_terrain(mb: &mut MapBuilder, rng: &mut RandomNumberGenerator, depth: u32) {
+    let mut water = FastNoise::seeded(rng.next_u64());
+    water.set_noise_type(NoiseType::Simplex);
+    water.set_frequency(NOISE_FREQUENCY);
+    let mut lava = FastNoise::seeded(rng.next_u64());
+    lava.set_noise_type(NoiseType::Simplex);
+    lava.set_frequency(NOISE_FREQUENCY);
+
+    let mut protected: HashSet<Point> = mb.monster_spawns.iter().copied().collect();
+    protected.insert(mb.player_start);
+    protected.insert(mb.amulet_start);
+    let reachable_before = reachable_tiles(mb);
+
+    for y in 1..mb.map.height - 1 {
+        for x in 1..mb.map.width - 1 {
+            let pt = Point::new(x, y);
+            let idx = mb.map.point2d_to_index(pt);
+            if mb.map.tiles[idx] != TileType::Floor
+                || protected.contains(&pt)
+                || mb.prefab.is_some_and(|r| r.point_in_rect(pt))
+            {
+                continue;
+            }
+
+            // The deep pools and the lava stay out of the corridors, so they never block
+            // the only way through.
+            let walls_around = count_walls_around(&mb.map, pt);
+            let water_level = water.get_noise(x as f32, y as f32);
+            mb.map.tiles[idx] = if water_level > DEEP_WATER_LEVEL && walls_around == 0 {
+                TileType::DeepWater
+            } else if water_level > SHALLOW_WATER_LEVEL {
+                TileType::ShallowWater
+            } else if depth > 0
+                && lava.get_noise(x as f32, y as f32) > LAVA_LEVEL
+                && walls_around == 0
+            {
+                TileType::Lava
+            } else if walls_around > 0 && rng.range(0, 100) < RUBBLE_CHANCE {
+                TileType::Rubble
+            } else {
+                TileType::Floor
+            };
+        }
+    }
+
+    // Pools can still cut a room in two, in which case they are made wadeable.
+    let reachable_after = reachable_tiles(mb);
+    if reachable_before
+        .iter()
+        .any(|idx| !reachable_after.contains(idx) && mb.map.tiles[*idx] != TileType::DeepWater)
+    {
+        mb.map
+            .tiles
+            .iter_mut()
+            .filter(|t| **t == TileType::DeepWater)
+            .for_each(|t| *t = TileType::ShallowWater);
+    }
+
+    // Lava can cut the level in two as well, leaving the only way on through it. It cools
+    // down into floor where it is walked into until the level is connected again.
+    loop {
+        let walkable = reachable_without_lava(mb);
+        if reachable_before.iter().all(|idx| {
+            walkable.contains(idx)
+                || matches!(mb.map.tiles[*idx], TileType::Lava | TileType::DeepWater)
+        }) {
+            break;
+        }
+        let shore: HashSet<usize> = walkable
+            .iter()
+            .flat_map(|idx| mb.map.get_available_exits(*idx))
+            .map(|(exit, _)| exit)
+            .filter(|exit| mb.map.tiles[*exit] == TileType::Lava)
+            .collect();
+        if shore.is_empty() {
+            break;
+        }
+        shore
+            .into_iter()
+            .for_each(|idx| mb.map.tiles[idx] = TileType::Floor);
+    }
+}
+
+fn count_walls_around(map: &Map, pt: Point) -> usize {
+    (-1..=1)
+        .flat_map(|dy| (-1..=1).map(move |dx| Point::new(dx, dy)))
+        .filter(|delta| *delta != Point::zero())
+        .filter(|delta| {
+            map.try_idx(pt + *delta)
+                .is_none_or(|idx| map.tiles[idx] == TileType::Wall)
+        })
+        .count()
+}
+
+fn reachable_tiles(mb: &MapBuilder) -> HashSet<usize> {
+    let dijkstra_map = DijkstraMap::new(
+        mb.map.width,
+        mb.map.height,
+        &[mb.map.point2d_to_index(mb.player_start)],
+        &mb.map,
+        f32::MAX,
+    );
+    dijkstra_map
+        .map
+        .iter()
+        .enumerate()
+        .filter(|(_, dist)| **dist < f32::MAX)
+        .map(|(idx, _)| idx)
+        .collect()
+}
+
+/// The tiles the player can walk to from the start without stepping onto lava.
+fn reachable_without_lava(mb: &MapBuilder) -> HashSet<usize> {
+    let start = mb.map.point2d_to_index(mb.player_start);
+    let mut reached = HashSet::from([start]);
+    let mut frontier = vec![start];
+    while let Some(idx) = frontier.pop() {
+        for (exit, _) in mb.map.get_available_exits(idx) {
+            if mb.map.tiles[exit] != TileType::Lava && reached.insert(exit) {
+                frontier.push(exit);
+            }
+        }
+    }
+    reached
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn terrain_keeps_the_level_connected() {
+        for seed in 0..20 {
+            let mut rng = RandomNumberGenerator::seeded(seed);
+            let config = GameConfig::default();
+            let mut mb = MapBuilder::new(&mut rng, &config);
+            let before = reachable_tiles(&mb);
+            place_terrain(&mut mb, &mut rng, 2);
+
+            let after = reachable_tiles(&mb);
+            assert!(before
+                .iter()
+                .all(|idx| after.contains(idx) || mb.map.tiles[*idx] == TileType::DeepWater));
+            assert!(after.contains(&mb.map.point2d_to_index(mb.amulet_start)));
+            let amulet_idx = mb.map.point2d_to_index(mb.amulet_start);
+            assert!(reachable_without_lava(&mb).contains(&amulet_idx));
+            assert_eq!(
+                mb.map.tiles[mb.map.point2d_to_index(mb.player_start)],
+                TileType::Floor
+            );
+        }
+    }
+
+    #[test]
+    fn lava_never_blocks_the_only_way() {
+        let mut rng = RandomNumberGenerator::seeded(1);
+        let mut mb =
+            MapBuilder::with_architect(ArchitectKind::Empty, &mut rng, &GameConfig::default());
+        mb.fill(TileType::Wall);
+        mb.monster_spawns.clear();
+        mb.prefab = None;
+        for x in 1..20 {
+            let idx = mb.map.map_idx(x, 5);
+            mb.map.tiles[idx] = TileType::Floor;
+        }
+        let lava_idx = mb.map.map_idx(10, 5);
+        mb.map.tiles[lava_idx] = TileType::Lava;
+        mb.player_start = Point::new(1, 5);
+        mb.amulet_start = Point::new(19, 5);
+
+        place_terrain(&mut mb, &mut rng, 2);
+        assert_eq!(mb.map.tiles[lava_idx], TileType::Floor);
+        assert!(reachable_without_lava(&mb).contains(&mb.map.map_idx(19, 5)));
+    }
+}
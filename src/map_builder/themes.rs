@@ -1,37 +1,91 @@
 use crate::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// Identifies a theme, so the selection can be stored and restored later.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum ThemeKind {
+    Dungeon,
+    Forest,
+}
+
+impl ThemeKind {
+    pub fn build(self) -> Box<dyn MapTheme> {
+        match self {
+            ThemeKind::Dungeon => DungeonTheme::new(),
+            ThemeKind::Forest => ForestTheme::new(),
+        }
+    }
+}
 
 pub struct DungeonTheme {}
 
 impl DungeonTheme {
+    #[allow(clippy::new_ret_no_self)]
     pub fn new() -> Box<dyn MapTheme> {
         Box::new(Self {})
     }
 }
 
 impl MapTheme for DungeonTheme {
+    fn kind(&self) -> ThemeKind {
+        ThemeKind::Dungeon
+    }
+
     fn tile_to_render(&self, tile_type: TileType) -> FontCharType {
         match tile_type {
             TileType::Floor => to_cp437('.'),
             TileType::Wall => to_cp437('#'),
             TileType::Exit => to_cp437('>'),
+            TileType::UpStairs => to_cp437('<'),
+            TileType::DoorClosed => to_cp437('+'),
+            TileType::DoorOpen => to_cp437('\''),
+            // Secret doors pass for walls until they are found.
+            TileType::DoorSecret => to_cp437('#'),
+            TileType::ShallowWater => to_cp437('~'),
+            TileType::DeepWater => to_cp437('≈'),
+            TileType::Lava => to_cp437('▒'),
+            TileType::Rubble => to_cp437(':'),
         }
     }
+
+    /// Deep underground, only the light sources light the way.
+    fn ambient_light(&self) -> RGB {
+        RGB::from_f32(0.1, 0.1, 0.15)
+    }
 }
 
 pub struct ForestTheme {}
 
 impl ForestTheme {
+    #[allow(clippy::new_ret_no_self)]
     pub fn new() -> Box<dyn MapTheme> {
         Box::new(Self {})
     }
 }
 
 impl MapTheme for ForestTheme {
+    fn kind(&self) -> ThemeKind {
+        ThemeKind::Forest
+    }
+
     fn tile_to_render(&self, tile_type: TileType) -> FontCharType {
         match tile_type {
             TileType::Floor => to_cp437(';'),
             TileType::Wall => to_cp437('"'),
             TileType::Exit => to_cp437('>'),
+            TileType::UpStairs => to_cp437('<'),
+            TileType::DoorClosed => to_cp437('+'),
+            TileType::DoorOpen => to_cp437('\''),
+            TileType::DoorSecret => to_cp437('"'),
+            TileType::ShallowWater => to_cp437(','),
+            TileType::DeepWater => to_cp437('≈'),
+            TileType::Lava => to_cp437('▒'),
+            TileType::Rubble => to_cp437('`'),
         }
     }
+
+    /// The forest is moonlit, bright enough to see who lurks in it.
+    fn ambient_light(&self) -> RGB {
+        RGB::from_f32(0.4, 0.45, 0.7)
+    }
 }
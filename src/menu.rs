@@ -0,0 +1,202 @@
+use crate::prelude::*;
+
+struct MenuItem<T> {
+    label: String,
+    action: T,
+    enabled: bool,
+}
+
+/// A vertical list of options, drawn centered on the active console and navigable with
+/// the cursor keys (or the number keys) and the mouse.
+pub struct Menu<T: Copy> {
+    items: Vec<MenuItem<T>>,
+    selected: usize,
+    /// Only a moving mouse changes the selection, so it doesn't fight the keyboard.
+    last_mouse_pos: Point,
+}
+
+impl<T: Copy + PartialEq> Menu<T> {
+    pub fn new(items: &[(&str, T)]) -> Self {
+        Self {
+            items: items
+                .iter()
+                .map(|(label, action)| MenuItem {
+                    label: label.to_string(),
+                    action: *action,
+                    enabled: true,
+                })
+                .collect(),
+            selected: 0,
+            last_mouse_pos: Point::new(-1, -1),
+        }
+    }
+
+    pub fn set_enabled(&mut self, action: T, enabled: bool) {
+        self.items
+            .iter_mut()
+            .filter(|item| item.action == action)
+            .for_each(|item| item.enabled = enabled);
+        if !self.items[self.selected].enabled {
+            self.move_selection(1);
+        }
+    }
+
+    pub fn set_label(&mut self, action: T, label: &str) {
+        self.items
+            .iter_mut()
+            .filter(|item| item.action == action)
+            .for_each(|item| item.label = label.to_string());
+    }
+
+    fn move_selection(&mut self, delta: i32) {
+        let len = self.items.len() as i32;
+        let mut selected = self.selected as i32;
+        for _ in 0..len {
+            selected = (selected + delta).rem_euclid(len);
+            if self.items[selected as usize].enabled {
+                break;
+            }
+        }
+        self.selected = selected as usize;
+    }
+
+    /// The console row of the n-th item, for a menu drawn starting at `top_y`.
+    fn item_row(top_y: i32, n: usize) -> i32 {
+        top_y + n as i32 * 2
+    }
+
+    /// Handles the input of a frame, returning the action of the item that was chosen.
+    /// `mouse_pos` must be in the coordinates of the console the menu is drawn on.
+    pub fn update(
+        &mut self,
+        key: Option<VirtualKeyCode>,
+        mouse_pos: Point,
+        clicked: bool,
+        top_y: i32,
+    ) -> Option<T> {
+        let mouse_moved = mouse_pos != self.last_mouse_pos;
+        self.last_mouse_pos = mouse_pos;
+        if let Some(hovered) =
+            (0..self.items.len()).find(|n| Self::item_row(top_y, *n) == mouse_pos.y)
+        {
+            if self.items[hovered].enabled {
+                if mouse_moved || clicked {
+                    self.selected = hovered;
+                }
+                if clicked {
+                    return Some(self.items[hovered].action);
+                }
+            }
+        }
+
+        let chosen = match key? {
+            VirtualKeyCode::Up => {
+                self.move_selection(-1);
+                None
+            }
+            VirtualKeyCode::Down => {
+                self.move_selection(1);
+                None
+            }
+            VirtualKeyCode::Return | VirtualKeyCode::NumpadEnter => Some(self.selected),
+            VirtualKeyCode::Key1 => Some(0),
+            VirtualKeyCode::Key2 => Some(1),
+            VirtualKeyCode::Key3 => Some(2),
+            VirtualKeyCode::Key4 => Some(3),
+            VirtualKeyCode::Key5 => Some(4),
+            VirtualKeyCode::Key6 => Some(5),
+            VirtualKeyCode::Key7 => Some(6),
+            VirtualKeyCode::Key8 => Some(7),
+            VirtualKeyCode::Key9 => Some(8),
+            _ => None,
+        };
+        chosen
+            .and_then(|n| self.items.get(n))
+            .filter(|item| item.enabled)
+            .map(|item| item.action)
+    }
+
+    /// Draws the menu on the active console, starting at row `top_y`.
+    pub fn render(&self, ctx: &mut BTerm, top_y: i32) {
+        self.items.iter().enumerate().for_each(|(n, item)| {
+            let label = format!("{}. {}", n + 1, item.label);
+            let (fg, bg) = if !item.enabled {
+                (DARK_GRAY, BLACK)
+            } else if n == self.selected {
+                (BLACK, YELLOW)
+            } else {
+                (WHITE, BLACK)
+            };
+            ctx.print_color_centered(Self::item_row(top_y, n), fg, bg, label);
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    enum Action {
+        First,
+        Second,
+        Third,
+    }
+
+    fn build_menu() -> Menu<Action> {
+        Menu::new(&[
+            ("First", Action::First),
+            ("Second", Action::Second),
+            ("Third", Action::Third),
+        ])
+    }
+
+    #[test]
+    fn keyboard_navigation_skips_disabled_items() {
+        let mut menu = build_menu();
+        menu.set_enabled(Action::Second, false);
+        let nowhere = Point::new(-1, -1);
+
+        assert_eq!(
+            menu.update(Some(VirtualKeyCode::Down), nowhere, false, 10),
+            None
+        );
+        assert_eq!(
+            menu.update(Some(VirtualKeyCode::Return), nowhere, false, 10),
+            Some(Action::Third)
+        );
+        assert_eq!(
+            menu.update(Some(VirtualKeyCode::Key2), nowhere, false, 10),
+            None
+        );
+        assert_eq!(
+            menu.update(Some(VirtualKeyCode::Key1), nowhere, false, 10),
+            Some(Action::First)
+        );
+    }
+
+    #[test]
+    fn mouse_click_chooses_hovered_item() {
+        let mut menu = build_menu();
+
+        assert_eq!(menu.update(None, Point::new(5, 12), false, 10), None);
+        assert_eq!(
+            menu.update(None, Point::new(5, 12), true, 10),
+            Some(Action::Second)
+        );
+        assert_eq!(menu.update(None, Point::new(5, 13), true, 10), None);
+    }
+
+    #[test]
+    fn still_mouse_does_not_override_keyboard() {
+        let mut menu = build_menu();
+        let hovering_first = Point::new(5, 10);
+
+        menu.update(None, hovering_first, false, 10);
+        menu.update(Some(VirtualKeyCode::Down), hovering_first, false, 10);
+        assert_eq!(
+            menu.update(Some(VirtualKeyCode::Return), hovering_first, false, 10),
+            Some(Action::Second)
+        );
+    }
+}
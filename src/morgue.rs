@@ -0,0 +1,229 @@
+use crate::prelude::*;
+use std::fmt::Write;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// The directory the morgue files are written to.
+pub const MORGUE_DIR: &str = "morgue";
+
+/// How many of the last messages are included in a morgue file.
+const MORGUE_MESSAGES: usize = 15;
+
+/// How far around the player the map snapshot reaches.
+const SNAPSHOT_HALF_WIDTH: i32 = 30;
+const SNAPSHOT_HALF_HEIGHT: i32 = 12;
+
+/// Formats a time as `YYYY-MM-DD HH:MM:SS` in UTC.
+pub fn format_utc(time: SystemTime) -> String {
+    let secs = time.duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs()) as i64;
+    let (days, secs_of_day) = (secs.div_euclid(86400), secs.rem_euclid(86400));
+
+    // Converts the days since 1970-01-01 to a civil date, from Howard Hinnant's algorithms.
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}:{:02}",
+        year,
+        month,
+        day,
+        secs_of_day / 3600,
+        secs_of_day % 3600 / 60,
+        secs_of_day % 60
+    )
+}
+
+/// A plain text summary of a finished run, for comparing runs when tuning the game.
+pub fn morgue_text(sim: &Simulation) -> String {
+    let (player, player_entity, player_pos, health) = <(&Player, Entity, &Point, &Health)>::query()
+        .iter(&sim.ecs)
+        .next()
+        .expect("Player is missing");
+    let turns = sim.resources.get::<TurnCount>().map_or(0, |t| t.0);
+
+    let mut text = String::new();
+    writeln!(text, "Rusty Roguelike morgue file").unwrap();
+    writeln!(text, "Date:  {} UTC", format_utc(SystemTime::now())).unwrap();
+    writeln!(text, "Seed:  {}", sim.seed).unwrap();
+    writeln!(
+        text,
+        "Depth: dungeon level {} of {}",
+        player.map_level + 1,
+        sim.config.dungeon_depth
+    )
+    .unwrap();
+    writeln!(text, "Turns: {}", turns).unwrap();
+    writeln!(text, "HP:    {} / {}", health.current, health.max).unwrap();
+    writeln!(text, "Goal:  {}", sim.config.victory.describe(turns)).unwrap();
+    let outcome = match sim.turn_state() {
+        TurnState::Victory => format!("Victory! {}", sim.config.victory.accomplishment()),
+        _ => match sim
+            .ecs
+            .entry_ref(*player_entity)
+            .unwrap()
+            .get_component::<SlainBy>()
+        {
+            Ok(slain_by) => fate(slain_by),
+            Err(_) => "Still alive.".to_string(),
+        },
+    };
+    writeln!(text, "Fate:  {}", outcome).unwrap();
+
+    writeln!(text, "\nInventory:").unwrap();
+    let inventory: Vec<String> = <(&Name, &Carried)>::query()
+        .filter(component::<Item>())
+        .iter(&sim.ecs)
+        .filter(|(_, carried)| carried.0 == *player_entity)
+        .map(|(name, _)| name.0.clone())
+        .collect();
+    if inventory.is_empty() {
+        writeln!(text, "  (nothing)").unwrap();
+    }
+    inventory
+        .iter()
+        .for_each(|name| writeln!(text, "  {}", name).unwrap());
+
+    writeln!(text, "\nStatistics:").unwrap();
+    sim.stats_summary()
+        .iter()
+        .for_each(|line| writeln!(text, "  {}", line).unwrap());
+
+    writeln!(text, "\nLast messages:").unwrap();
+    if let Some(log) = sim.resources.get::<GameLog>() {
+        let entries = log.entries();
+        entries[entries.len().saturating_sub(MORGUE_MESSAGES)..]
+            .iter()
+            .for_each(|entry| writeln!(text, "  {}", entry.text).unwrap());
+    }
+
+    writeln!(text, "\nMap around the player:").unwrap();
+    text.push_str(&map_snapshot(sim, *player_pos));
+    text
+}
+
+/// How the player died, in a sentence.
+fn fate(slain_by: &SlainBy) -> String {
+    match slain_by {
+        SlainBy::Monster(name) => format!("Slain by {}.", with_article(name)),
+        SlainBy::Trap(name) => format!("Killed by {}.", with_article(name)),
+        SlainBy::Poison(name) => format!("Poisoned by {}.", with_article(name)),
+        SlainBy::Lava => "Burned to death in lava.".to_string(),
+    }
+}
+
+/// Puts "a" or "an" in front of `name`, depending on its first letter.
+fn with_article(name: &str) -> String {
+    let vowel = name
+        .chars()
+        .next()
+        .is_some_and(|c| "aeiouAEIOU".contains(c));
+    format!("{} {}", if vowel { "an" } else { "a" }, name)
+}
+
+/// The revealed tiles around `center`, with the entities the player can see on them.
+fn map_snapshot(sim: &Simulation, center: Point) -> String {
+    let map = sim.resources.get::<Map>().expect("Map is missing");
+    let visible = <&FieldOfView>::query()
+        .filter(component::<Player>())
+        .iter(&sim.ecs)
+        .next()
+        .map(|fov| fov.visible_tiles.clone())
+        .unwrap_or_default();
+    let entities: Vec<(Point, char)> = <(&Point, &Render)>::query()
+        .filter(!component::<Hidden>())
+        .iter(&sim.ecs)
+        .filter(|(pos, _)| visible.contains(pos) && **pos != center)
+        .map(|(pos, render)| (*pos, to_char(render.glyph as u8)))
+        .collect();
+
+    let mut snapshot = String::new();
+    for y in center.y - SNAPSHOT_HALF_HEIGHT..=center.y + SNAPSHOT_HALF_HEIGHT {
+        snapshot.push_str("  ");
+        for x in center.x - SNAPSHOT_HALF_WIDTH..=center.x + SNAPSHOT_HALF_WIDTH {
+            let pt = Point::new(x, y);
+            let entity = entities.iter().find(|(pos, _)| *pos == pt);
+            let c = match map.try_idx(pt) {
+                _ if pt == center => '@',
+                Some(_) if entity.is_some() => entity.unwrap().1,
+                Some(idx) if map.revealed_tiles[idx] => map.tiles[idx].to_ascii(),
+                _ => ' ',
+            };
+            snapshot.push(c);
+        }
+        snapshot.truncate(snapshot.trim_end().len());
+        snapshot.push('\n');
+    }
+    snapshot
+}
+
+/// Writes the morgue file of the run to `MORGUE_DIR`, returning its path.
+pub fn write_morgue(sim: &Simulation) -> std::io::Result<PathBuf> {
+    std::fs::create_dir_all(MORGUE_DIR)?;
+    let secs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs());
+    let path = PathBuf::from(MORGUE_DIR).join(format!("morgue-{}-{}.txt", sim.seed, secs));
+    std::fs::write(&path, morgue_text(sim))?;
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn utc_dates_are_formatted() {
+        assert_eq!(format_utc(UNIX_EPOCH), "1970-01-01 00:00:00");
+        assert_eq!(
+            format_utc(UNIX_EPOCH + Duration::from_secs(1_709_210_096)),
+            "2024-02-29 12:34:56"
+        );
+    }
+
+    #[test]
+    fn morgue_describes_the_death() {
+        let mut sim = Simulation::headless(5);
+        let player = *<Entity>::query()
+            .filter(component::<Player>())
+            .iter(&sim.ecs)
+            .next()
+            .unwrap();
+        sim.ecs
+            .push((Item, Name("Rusty Sword".to_string()), Carried(player)));
+        sim.ecs
+            .entry(player)
+            .unwrap()
+            .add_component(SlainBy::Monster("Orc".to_string()));
+        sim.resources
+            .get_mut::<GameLog>()
+            .unwrap()
+            .add("The Orc hits you for 2.", RED);
+        sim.set_turn_state(TurnState::GameOver);
+
+        let text = morgue_text(&sim);
+        assert!(text.contains("Seed:  5"));
+        assert!(text.contains("Depth: dungeon level 1 of 3"));
+        assert!(text.contains("Fate:  Slain by an Orc."));
+        assert!(text.contains("  Rusty Sword"));
+        assert!(text.contains("  Monsters killed: 0"));
+        assert!(text.contains("  The Orc hits you for 2."));
+        assert!(text.contains('@'));
+    }
+
+    #[test]
+    fn hazards_have_their_own_fate() {
+        assert_eq!(fate(&SlainBy::Lava), "Burned to death in lava.");
+        assert_eq!(
+            fate(&SlainBy::Trap("Spike Trap".to_string())),
+            "Killed by a Spike Trap."
+        );
+    }
+}
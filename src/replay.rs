@@ -0,0 +1,214 @@
+use crate::prelude::*;
+
+use ron::ser::PrettyConfig;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// The input of one or more consecutive frames, as fed into `Simulation::tick`.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ReplayFrame {
+    pub key: Option<KeyCode>,
+    pub mouse: Point,
+    /// How many frames in a row had this exact input.
+    pub ticks: u32,
+}
+
+/// A summary of where a run ended, used to check that a replay reproduces it.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ReplayOutcome {
+    pub turn_state: TurnState,
+    pub map_level: u32,
+    pub player_pos: Point,
+    pub player_hp: i32,
+}
+
+impl ReplayOutcome {
+    pub fn of(sim: &Simulation) -> Self {
+        let (player, player_pos, health) = <(&Player, &Point, &Health)>::query()
+            .iter(&sim.ecs)
+            .next()
+            .unwrap();
+        Self {
+            turn_state: sim.turn_state(),
+            map_level: player.map_level,
+            player_pos: *player_pos,
+            player_hp: health.current,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct Replay {
+    pub seed: u64,
+    pub frames: Vec<ReplayFrame>,
+    /// The settings the run was recorded with, so the game and the keys behave the same
+    /// on playback.
+    #[serde(default)]
+    pub config: GameConfig,
+    #[serde(default)]
+    pub key_bindings: KeyBindings,
+    /// The state the run was in after its last recorded frame.
+    pub outcome: Option<ReplayOutcome>,
+}
+
+impl Replay {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            ..Default::default()
+        }
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> ron::Result<Self> {
+        ron::from_str(&std::fs::read_to_string(path)?)
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> ron::Result<()> {
+        let replay = ron::ser::to_string_pretty(self, PrettyConfig::default())?;
+        std::fs::write(path, replay)?;
+        Ok(())
+    }
+
+    pub fn record(&mut self, key: Option<VirtualKeyCode>, mouse: Point) {
+        let key = key.map(KeyCode::from);
+        match self.frames.last_mut() {
+            Some(last) if last.key == key && last.mouse == mouse => last.ticks += 1,
+            _ => self.frames.push(ReplayFrame {
+                key,
+                mouse,
+                ticks: 1,
+            }),
+        }
+    }
+
+    /// Iterates the recorded input of every single frame.
+    pub fn inputs(&self) -> impl Iterator<Item = (Option<VirtualKeyCode>, Point)> + '_ {
+        self.frames.iter().flat_map(|frame| {
+            let key = frame.key.map(VirtualKeyCode::from);
+            std::iter::repeat_n((key, frame.mouse), frame.ticks as usize)
+        })
+    }
+
+    /// Plays the whole replay on a headless simulation.
+    pub fn play_headless(&self) -> Simulation {
+        let mut sim = Simulation::with_settings(
+            self.seed,
+            false,
+            self.config.clone(),
+            self.key_bindings.clone(),
+        );
+        self.inputs().for_each(|(key, mouse)| sim.tick(key, mouse));
+        sim
+    }
+}
+
+/// Records the input of a run, rewriting the replay file whenever a key is pressed or
+/// the turn state changes, so an interrupted session still leaves a usable replay.
+pub struct ReplayRecorder {
+    replay: Replay,
+    path: PathBuf,
+    last_state: Option<TurnState>,
+}
+
+impl ReplayRecorder {
+    pub fn new<P: Into<PathBuf>>(path: P, seed: u64) -> Self {
+        Self {
+            replay: Replay::new(seed),
+            path: path.into(),
+            last_state: None,
+        }
+    }
+
+    /// Records a frame that was just fed into `sim`.
+    pub fn record(
+        &mut self,
+        key: Option<VirtualKeyCode>,
+        mouse: Point,
+        sim: &Simulation,
+    ) -> ron::Result<()> {
+        self.replay.record(key, mouse);
+
+        let state = sim.turn_state();
+        if key.is_some() || self.last_state != Some(state) {
+            self.last_state = Some(state);
+            self.replay.outcome = Some(ReplayOutcome::of(sim));
+            self.replay.config = sim.config.clone();
+            self.replay.key_bindings = sim.key_bindings.clone();
+            self.replay.save(&self.path)?;
+        }
+        Ok(())
+    }
+}
+
+/// Feeds the frames of a replay into a windowed game, `speed` frames per rendered frame.
+pub struct ReplayPlayer {
+    inputs: Vec<(Option<VirtualKeyCode>, Point)>,
+    position: usize,
+    pub speed: usize,
+}
+
+impl ReplayPlayer {
+    pub fn new(replay: &Replay, speed: usize) -> Self {
+        Self {
+            inputs: replay.inputs().collect(),
+            position: 0,
+            speed: speed.max(1),
+        }
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.position >= self.inputs.len()
+    }
+
+    pub fn next_input(&mut self) -> Option<(Option<VirtualKeyCode>, Point)> {
+        let input = self.inputs.get(self.position).copied();
+        self.position += 1;
+        input
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn consecutive_frames_are_merged() {
+        let mut replay = Replay::new(1);
+        replay.record(None, Point::new(1, 1));
+        replay.record(None, Point::new(1, 1));
+        replay.record(Some(VirtualKeyCode::Left), Point::new(1, 1));
+        replay.record(None, Point::new(1, 1));
+
+        assert_eq!(replay.frames.len(), 3);
+        assert_eq!(replay.frames[0].ticks, 2);
+        assert_eq!(replay.inputs().count(), 4);
+    }
+
+    #[test]
+    fn replay_reaches_the_recorded_outcome() {
+        let seed = 1234;
+        let mut sim = Simulation::headless(seed);
+        let mut replay = Replay::new(seed);
+        let keys = [
+            Some(VirtualKeyCode::Right),
+            None,
+            None,
+            Some(VirtualKeyCode::Down),
+            None,
+            None,
+            Some(VirtualKeyCode::Left),
+            None,
+            None,
+        ];
+        for key in keys {
+            sim.tick(key, Point::zero());
+            replay.record(key, Point::zero());
+        }
+        replay.outcome = Some(ReplayOutcome::of(&sim));
+
+        let text = ron::ser::to_string(&replay).unwrap();
+        let loaded: Replay = ron::from_str(&text).unwrap();
+        let replayed = loaded.play_headless();
+        assert_eq!(Some(ReplayOutcome::of(&replayed)), loaded.outcome);
+    }
+}
@@ -0,0 +1,92 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// What happened during the run, for balancing the game and for the high-score table.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct RunStats {
+    /// The deepest level reached, counting from 0.
+    pub deepest_level: u32,
+    /// How many tiles the player has revealed by walking around, on every level.
+    pub tiles_explored: u32,
+    pub damage_dealt: u32,
+    pub damage_taken: u32,
+    /// The monsters killed by the player, by name.
+    pub kills: BTreeMap<String, u32>,
+    pub items_used: u32,
+    pub potions_drunk: u32,
+    /// The turns spent on each level the player has left, counting from level 0.
+    pub turns_per_level: BTreeMap<u32, u32>,
+    /// The turn the player arrived on the current level.
+    pub level_entered_at: u32,
+}
+
+impl RunStats {
+    pub fn add_kill(&mut self, name: &str) {
+        *self.kills.entry(name.to_string()).or_default() += 1;
+    }
+
+    pub fn total_kills(&self) -> u32 {
+        self.kills.values().sum()
+    }
+
+    /// Records the player leaving `map_level` for `target_level` on turn `turn`.
+    pub fn change_level(&mut self, map_level: u32, target_level: u32, turn: u32) {
+        *self.turns_per_level.entry(map_level).or_default() +=
+            turn.saturating_sub(self.level_entered_at);
+        self.level_entered_at = turn;
+        self.deepest_level = self.deepest_level.max(target_level);
+    }
+
+    /// The turns spent on each level, including the time spent so far on `map_level`, the
+    /// current one.
+    pub fn time_per_level(&self, map_level: u32, turn: u32) -> BTreeMap<u32, u32> {
+        let mut time = self.turns_per_level.clone();
+        *time.entry(map_level).or_default() += turn.saturating_sub(self.level_entered_at);
+        time
+    }
+
+    /// One line per statistic, as shown on the stats screen and in the morgue file.
+    pub fn summary(&self, map_level: u32, turn: u32) -> Vec<String> {
+        let mut lines = vec![
+            format!("Turns taken:     {}", turn),
+            format!("Deepest level:   {}", self.deepest_level + 1),
+            format!("Tiles explored:  {}", self.tiles_explored),
+            format!("Damage dealt:    {}", self.damage_dealt),
+            format!("Damage taken:    {}", self.damage_taken),
+            format!("Items used:      {}", self.items_used),
+            format!("Potions drunk:   {}", self.potions_drunk),
+            format!("Monsters killed: {}", self.total_kills()),
+        ];
+        lines.extend(
+            self.kills
+                .iter()
+                .map(|(name, count)| format!("  {:<15}{}", name, count)),
+        );
+        lines.push("Turns per level:".to_string());
+        lines.extend(
+            self.time_per_level(map_level, turn)
+                .iter()
+                .map(|(level, turns)| format!("  Level {:<9}{}", level + 1, turns)),
+        );
+        lines
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn time_is_split_between_the_levels() {
+        let mut stats = RunStats::default();
+        stats.change_level(0, 1, 10);
+        stats.change_level(1, 0, 25);
+        stats.change_level(0, 1, 30);
+
+        assert_eq!(stats.deepest_level, 1);
+        assert_eq!(stats.turns_per_level[&0], 15);
+        let time = stats.time_per_level(1, 32);
+        assert_eq!(time[&0], 15);
+        assert_eq!(time[&1], 17);
+    }
+}
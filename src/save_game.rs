@@ -0,0 +1,370 @@
+use crate::prelude::*;
+
+use legion::serialize::Canon;
+use ron::ser::PrettyConfig;
+use serde::de::{self, DeserializeSeed, Deserializer, SeqAccess, Visitor};
+use serde::ser::{SerializeSeq, Serializer};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::path::Path;
+
+pub const SAVE_FILE: &str = "savegame.ron";
+
+/// Everything that is needed to restore a run besides the `World` itself.
+#[derive(Serialize, Deserialize)]
+struct SaveHeader {
+    seed: u64,
+    /// The config the run started with, as the config file may have changed since.
+    config: GameConfig,
+    map: Map,
+    camera: Camera,
+    theme: ThemeKind,
+    rng: RandomNumberGenerator,
+    turn_count: TurnCount,
+    log: GameLog,
+    stats: RunStats,
+}
+
+/// Writes the levels stored in the `Dungeon` as a list of `(level, Map, ThemeKind, World)`.
+struct SerializableLevels<'a> {
+    dungeon: &'a Dungeon,
+    registry: &'a Registry<String>,
+    canon: &'a Canon,
+}
+
+impl Serialize for SerializableLevels<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut seq = serializer.serialize_seq(Some(self.dungeon.levels.len()))?;
+        for (map_level, level) in &self.dungeon.levels {
+            let world = level
+                .world
+                .as_serializable(legion::any(), self.registry, self.canon);
+            seq.serialize_element(&(map_level, &level.map, level.theme, world))?;
+        }
+        seq.end()
+    }
+}
+
+/// Reads the list written by `SerializableLevels` back into a `Dungeon`.
+struct DungeonSeed<'a> {
+    registry: &'a Registry<String>,
+    canon: &'a Canon,
+}
+
+impl<'de> DeserializeSeed<'de> for DungeonSeed<'_> {
+    type Value = Dungeon;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        deserializer.deserialize_seq(self)
+    }
+}
+
+impl<'de> Visitor<'de> for DungeonSeed<'_> {
+    type Value = Dungeon;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a list of stored levels")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let mut dungeon = Dungeon::default();
+        while let Some((map_level, level)) = seq.next_element_seed(StoredLevelSeed {
+            registry: self.registry,
+            canon: self.canon,
+        })? {
+            dungeon.levels.insert(map_level, level);
+        }
+        Ok(dungeon)
+    }
+}
+
+/// Reads a single `(level, Map, ThemeKind, World)` tuple.
+struct StoredLevelSeed<'a> {
+    registry: &'a Registry<String>,
+    canon: &'a Canon,
+}
+
+impl<'de> DeserializeSeed<'de> for StoredLevelSeed<'_> {
+    type Value = (u32, StoredLevel);
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        deserializer.deserialize_tuple(4, self)
+    }
+}
+
+impl<'de> Visitor<'de> for StoredLevelSeed<'_> {
+    type Value = (u32, StoredLevel);
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a stored level")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let map_level = seq
+            .next_element::<u32>()?
+            .ok_or_else(|| de::Error::invalid_length(0, &self))?;
+        let map = seq
+            .next_element::<Map>()?
+            .ok_or_else(|| de::Error::invalid_length(1, &self))?;
+        let theme = seq
+            .next_element::<ThemeKind>()?
+            .ok_or_else(|| de::Error::invalid_length(2, &self))?;
+        let world = seq
+            .next_element_seed(self.registry.as_deserialize(self.canon))?
+            .ok_or_else(|| de::Error::invalid_length(3, &self))?;
+        Ok((map_level, StoredLevel { map, theme, world }))
+    }
+}
+
+/// Reads a `(SaveHeader, World, levels)` tuple, as the worlds can only be deserialized
+/// through the component registry.
+struct SaveGameSeed<'a> {
+    registry: &'a Registry<String>,
+    canon: &'a Canon,
+}
+
+impl<'de> DeserializeSeed<'de> for SaveGameSeed<'_> {
+    type Value = (SaveHeader, World, Dungeon);
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        deserializer.deserialize_tuple(3, self)
+    }
+}
+
+impl<'de> Visitor<'de> for SaveGameSeed<'_> {
+    type Value = (SaveHeader, World, Dungeon);
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a saved game")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let header = seq
+            .next_element::<SaveHeader>()?
+            .ok_or_else(|| de::Error::invalid_length(0, &self))?;
+        let world = seq
+            .next_element_seed(self.registry.as_deserialize(self.canon))?
+            .ok_or_else(|| de::Error::invalid_length(1, &self))?;
+        let dungeon = seq
+            .next_element_seed(DungeonSeed {
+                registry: self.registry,
+                canon: self.canon,
+            })?
+            .ok_or_else(|| de::Error::invalid_length(2, &self))?;
+        Ok((header, world, dungeon))
+    }
+}
+
+/// All the components that are persisted in a saved game.
+fn build_registry() -> Registry<String> {
+    let mut registry = Registry::<String>::default();
+    registry.register::<Point>("point".to_string());
+    registry.register::<Render>("render".to_string());
+    registry.register::<Player>("player".to_string());
+    registry.register::<Enemy>("enemy".to_string());
+    registry.register::<MovingRandomly>("moving_randomly".to_string());
+    registry.register::<ChasingPlayer>("chasing_player".to_string());
+    registry.register::<WantsToMove>("wants_to_move".to_string());
+    registry.register::<WantsToAttack>("wants_to_attack".to_string());
+    registry.register::<Health>("health".to_string());
+    registry.register::<Name>("name".to_string());
+    registry.register::<Item>("item".to_string());
+    registry.register::<AmuletOfYala>("amulet_of_yala".to_string());
+    registry.register::<Boss>("boss".to_string());
+    registry.register::<SlainBy>("slain_by".to_string());
+    registry.register::<FieldOfView>("field_of_view".to_string());
+    registry.register::<ProvidesHealing>("provides_healing".to_string());
+    registry.register::<ProvidesDungeonMap>("provides_dungeon_map".to_string());
+    registry.register::<Carried>("carried".to_string());
+    registry.register::<ActivateItem>("activate_item".to_string());
+    registry.register::<Damage>("damage".to_string());
+    registry.register::<Weapon>("weapon".to_string());
+    registry.register::<Speed>("speed".to_string());
+    registry.register::<Energy>("energy".to_string());
+    registry.register::<Trap>("trap".to_string());
+    registry.register::<Hidden>("hidden".to_string());
+    registry.register::<Teleports>("teleports".to_string());
+    registry.register::<SoundsAlarm>("sounds_alarm".to_string());
+    registry.register::<Poisons>("poisons".to_string());
+    registry.register::<Poisoned>("poisoned".to_string());
+    registry.register::<Alerted>("alerted".to_string());
+    registry.register::<LightSource>("light_source".to_string());
+    registry.register::<Digger>("digger".to_string());
+    registry.register::<Digging>("digging".to_string());
+    registry.register::<Explodes>("explodes".to_string());
+    registry
+}
+
+/// Serializes the world and the level resources to a RON string.
+pub fn serialize_game(ecs: &World, resources: &Resources, seed: u64) -> ron::Result<String> {
+    let header = SaveHeader {
+        seed,
+        config: resources.get::<GameConfig>().unwrap().clone(),
+        map: resources.get::<Map>().unwrap().clone(),
+        camera: *resources.get::<Camera>().unwrap(),
+        theme: resources.get::<Box<dyn MapTheme>>().unwrap().kind(),
+        rng: resources.get::<RandomNumberGenerator>().unwrap().clone(),
+        turn_count: *resources.get::<TurnCount>().unwrap(),
+        log: resources.get::<GameLog>().unwrap().clone(),
+        stats: resources.get::<RunStats>().unwrap().clone(),
+    };
+
+    let registry = build_registry();
+    let canon = Canon::default();
+    let world = ecs.as_serializable(legion::any(), &registry, &canon);
+    let levels = SerializableLevels {
+        dungeon: &resources.get::<Dungeon>().unwrap(),
+        registry: &registry,
+        canon: &canon,
+    };
+    ron::ser::to_string_pretty(&(header, world, levels), PrettyConfig::default())
+}
+
+/// Restores a game serialized by `serialize_game`, returning the world, its resources
+/// and the seed of the run.
+pub fn deserialize_game(save: &str) -> ron::Result<(World, Resources, u64)> {
+    let registry = build_registry();
+    let canon = Canon::default();
+    let (header, ecs, dungeon) = ron::Options::default().from_str_seed(
+        save,
+        SaveGameSeed {
+            registry: &registry,
+            canon: &canon,
+        },
+    )?;
+
+    let mut resources = Resources::default();
+    resources.insert(header.config);
+    resources.insert(header.map);
+    resources.insert(header.camera);
+    resources.insert(TurnState::AwaitingInput);
+    resources.insert(header.theme.build());
+    resources.insert(header.rng);
+    resources.insert(dungeon);
+    resources.insert(header.turn_count);
+    resources.insert(header.log);
+    resources.insert(header.stats);
+
+    Ok((ecs, resources, header.seed))
+}
+
+pub fn save_game(ecs: &World, resources: &Resources, seed: u64) -> ron::Result<()> {
+    let save = serialize_game(ecs, resources, seed)?;
+    std::fs::write(SAVE_FILE, save)?;
+    Ok(())
+}
+
+/// Restores the saved game. The save is left in place, so the caller should delete it once
+/// the game is running again, so that a run can't be continued from the same point twice.
+pub fn load_game() -> ron::Result<(World, Resources, u64)> {
+    let save = std::fs::read_to_string(SAVE_FILE)?;
+    deserialize_game(&save)
+}
+
+/// Deletes the saved game, if there is one.
+pub fn delete_saved_game() -> std::io::Result<()> {
+    match std::fs::remove_file(SAVE_FILE) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e),
+        _ => Ok(()),
+    }
+}
+
+pub fn has_saved_game() -> bool {
+    Path::new(SAVE_FILE).exists()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn save_and_load_round_trip() {
+        let mut ecs = World::default();
+        let mut resources = Resources::default();
+
+        let mut map = Map::default();
+        let (exit_idx, revealed_idx) = (map.map_idx(3, 4), map.map_idx(5, 5));
+        map.tiles[exit_idx] = TileType::Exit;
+        map.revealed_tiles[revealed_idx] = true;
+        resources.insert(map);
+        resources.insert(Camera::new(Point::new(10, 10)));
+        resources.insert(ThemeKind::Forest.build());
+        resources.insert(RandomNumberGenerator::seeded(7));
+        resources.insert(TurnCount(42));
+        resources.insert(GameConfig {
+            dungeon_depth: 5,
+            ..Default::default()
+        });
+        let mut stats = RunStats::default();
+        stats.add_kill("Orc");
+        resources.insert(stats);
+        let mut log = GameLog::default();
+        log.add("You pick up the Healing Potion.", CYAN);
+        resources.insert(log);
+
+        let mut level_zero = World::default();
+        level_zero.push((Enemy, Point::new(2, 2), Name("Goblin".to_string())));
+        let mut dungeon = Dungeon::default();
+        dungeon.store(0, &mut level_zero, Map::default(), ThemeKind::Dungeon);
+        resources.insert(dungeon);
+
+        spawn_player(&mut ecs, Point::new(10, 10), &GameConfig::default());
+        let player = *<Entity>::query()
+            .filter(component::<Player>())
+            .iter(&ecs)
+            .next()
+            .unwrap();
+        ecs.push((Item, Name("Healing Potion".to_string()), Carried(player)));
+
+        let save = serialize_game(&ecs, &resources, 1234).unwrap();
+        let (loaded_ecs, loaded_resources, seed) = deserialize_game(&save).unwrap();
+
+        assert_eq!(seed, 1234);
+        assert_eq!(*loaded_resources.get::<TurnCount>().unwrap(), TurnCount(42));
+        assert_eq!(
+            loaded_resources.get::<GameConfig>().unwrap().dungeon_depth,
+            5
+        );
+        assert_eq!(loaded_resources.get::<RunStats>().unwrap().kills["Orc"], 1);
+        assert_eq!(
+            loaded_resources.get::<GameLog>().unwrap().entries()[0].text,
+            "You pick up the Healing Potion."
+        );
+        assert_eq!(loaded_ecs.len(), 2);
+        let map = loaded_resources.get::<Map>().unwrap();
+        assert_eq!(map.tiles[map.map_idx(3, 4)], TileType::Exit);
+        assert!(map.revealed_tiles[map.map_idx(5, 5)]);
+        assert_eq!(
+            loaded_resources.get::<Box<dyn MapTheme>>().unwrap().kind(),
+            ThemeKind::Forest
+        );
+        assert_eq!(
+            loaded_resources
+                .get_mut::<RandomNumberGenerator>()
+                .unwrap()
+                .next_u64(),
+            RandomNumberGenerator::seeded(7).next_u64()
+        );
+
+        // The carried item must still point to the (new) player entity.
+        let (loaded_player, pos) = <(Entity, &Point)>::query()
+            .filter(component::<Player>())
+            .iter(&loaded_ecs)
+            .next()
+            .unwrap();
+        assert_eq!(*pos, Point::new(10, 10));
+        let carried = <&Carried>::query().iter(&loaded_ecs).next().unwrap();
+        assert_eq!(carried.0, *loaded_player);
+
+        let mut dungeon = loaded_resources.get_mut::<Dungeon>().unwrap();
+        let mut level_zero = World::default();
+        let (_, theme) = dungeon.restore(0, &mut level_zero).unwrap();
+        assert_eq!(theme, ThemeKind::Dungeon);
+        let goblin_pos = <&Point>::query()
+            .filter(component::<Enemy>())
+            .iter(&level_zero)
+            .next()
+            .unwrap();
+        assert_eq!(*goblin_pos, Point::new(2, 2));
+    }
+}
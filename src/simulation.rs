@@ -0,0 +1,847 @@
+use crate::prelude::*;
+
+/// Owns a running game (its `World` and `Resources`) and advances the `TurnState` machine
+/// through the real schedules. It does not need a `BTerm`, so it can also run headless,
+/// in which case the render systems are left out of the schedules.
+pub struct Simulation {
+    pub seed: u64,
+    pub ecs: World,
+    pub resources: Resources,
+    /// The config and bindings are kept outside of `resources` so they survive `reset`.
+    pub config: GameConfig,
+    pub key_bindings: KeyBindings,
+    /// Generates the levels, with hooks that can be changed before the game starts.
+    pub level_loader: LevelLoader,
+    input_systems: Schedule,
+    player_systems: Schedule,
+    monster_systems: Schedule,
+}
+
+impl Simulation {
+    /// Starts a new game that renders to the active `BTerm` draw batches, using the game
+    /// config and key bindings files.
+    pub fn new(seed: u64) -> Result<Self, String> {
+        Ok(Self::with_settings(
+            seed,
+            true,
+            GameConfig::load()?,
+            KeyBindings::load(),
+        ))
+    }
+
+    /// Starts a new game without any of the render systems, using the default game config
+    /// and key bindings, so it doesn't depend on the files in the working directory.
+    pub fn headless(seed: u64) -> Self {
+        Self::with_settings(seed, false, GameConfig::default(), KeyBindings::default())
+    }
+
+    /// Restores a game from its world and resources, e.g. from a saved game. The game keeps
+    /// the config it was started with, which must be among the resources.
+    pub fn from_world(seed: u64, ecs: World, mut resources: Resources) -> Result<Self, String> {
+        let config = resources
+            .get::<GameConfig>()
+            .map(|config| config.clone())
+            .ok_or("The game config is missing")?;
+        config.check_victory()?;
+        let key_bindings = KeyBindings::load();
+        resources.insert(key_bindings.clone());
+        resources.insert(LightMap::default());
+        Ok(Self {
+            seed,
+            ecs,
+            resources,
+            config,
+            key_bindings,
+            level_loader: LevelLoader::default(),
+            input_systems: build_input_scheduler(true),
+            player_systems: build_player_scheduler(true),
+            monster_systems: build_monster_scheduler(true),
+        })
+    }
+
+    pub fn with_settings(
+        seed: u64,
+        render: bool,
+        config: GameConfig,
+        key_bindings: KeyBindings,
+    ) -> Self {
+        let mut sim = Self {
+            seed,
+            ecs: World::default(),
+            resources: Resources::default(),
+            config,
+            key_bindings,
+            level_loader: LevelLoader::default(),
+            input_systems: build_input_scheduler(render),
+            player_systems: build_player_scheduler(render),
+            monster_systems: build_monster_scheduler(render),
+        };
+        sim.reset(seed);
+        sim
+    }
+
+    pub fn turn_state(&self) -> TurnState {
+        *self.resources.get::<TurnState>().unwrap()
+    }
+
+    pub fn set_turn_state(&mut self, turn_state: TurnState) {
+        self.resources.insert(turn_state);
+    }
+
+    /// The statistics of the run so far, one per line.
+    pub fn stats_summary(&self) -> Vec<String> {
+        let map_level = <&Player>::query()
+            .iter(&self.ecs)
+            .next()
+            .expect("Player is missing")
+            .map_level;
+        let turn = self.resources.get::<TurnCount>().map_or(0, |t| t.0);
+        self.resources
+            .get::<RunStats>()
+            .expect("Run stats are missing")
+            .summary(map_level, turn)
+    }
+
+    /// Runs a single frame of the game with the given input. `GameOver`, `Victory`,
+    /// `SaveGame` and `MainMenu` are left for the caller to handle.
+    pub fn tick(&mut self, key: Option<VirtualKeyCode>, mouse_pos: Point) {
+        self.resources.insert(key);
+        self.resources.insert(mouse_pos);
+
+        match self.turn_state() {
+            TurnState::AwaitingInput => self
+                .input_systems
+                .execute(&mut self.ecs, &mut self.resources),
+            TurnState::PlayerTurn => self
+                .player_systems
+                .execute(&mut self.ecs, &mut self.resources),
+            TurnState::MonsterTurn => self
+                .monster_systems
+                .execute(&mut self.ecs, &mut self.resources),
+            TurnState::NextLevel => self.change_level(1),
+            TurnState::PreviousLevel => self.change_level(-1),
+            TurnState::GameOver
+            | TurnState::Victory
+            | TurnState::SaveGame
+            | TurnState::MainMenu => {}
+        }
+    }
+
+    /// Feeds a key press and keeps ticking until the game waits for input again (or ends).
+    pub fn step(&mut self, key: VirtualKeyCode) -> TurnState {
+        self.tick(Some(key), Point::zero());
+        while matches!(
+            self.turn_state(),
+            TurnState::PlayerTurn
+                | TurnState::MonsterTurn
+                | TurnState::NextLevel
+                | TurnState::PreviousLevel
+        ) {
+            self.tick(None, Point::zero());
+        }
+        self.turn_state()
+    }
+
+    /// Feeds a sequence of key presses, stopping early if the game ends.
+    pub fn run(&mut self, keys: &[VirtualKeyCode]) -> TurnState {
+        for key in keys {
+            if matches!(self.step(*key), TurnState::GameOver | TurnState::Victory) {
+                break;
+            }
+        }
+        self.turn_state()
+    }
+
+    /// Throws the current game away and starts a new one from `seed`.
+    pub fn reset(&mut self, seed: u64) {
+        self.seed = seed;
+        self.ecs = World::default();
+        self.resources = Resources::default();
+        self.resources.insert(RandomNumberGenerator::seeded(seed));
+        let level =
+            self.level_loader
+                .load(0, None, &mut self.ecs, &mut self.resources, &self.config);
+        self.enter_level(level.map, level.theme, level.player_start);
+        self.resources.insert(Dungeon::default());
+        self.resources.insert(TurnCount::default());
+        self.resources.insert(RunStats::default());
+        let mut log = GameLog::default();
+        log.add("You enter the dungeon.", WHITE);
+        self.resources.insert(log);
+        self.resources.insert(self.config.clone());
+        self.resources.insert(self.key_bindings.clone());
+    }
+
+    /// Takes the player down (`1`) or up (`-1`) the stairs. The level they leave is stored
+    /// in the `Dungeon`, and the level they reach is restored from it if it was visited
+    /// before, or generated otherwise.
+    fn change_level(&mut self, delta: i32) {
+        let map_level = <&Player>::query()
+            .iter(&self.ecs)
+            .next()
+            .expect("Player is missing")
+            .map_level;
+        let target_level = (map_level as i32 + delta) as u32;
+
+        let mut dungeon = self
+            .resources
+            .remove::<Dungeon>()
+            .expect("Dungeon is missing");
+        let map = self.resources.remove::<Map>().expect("Map is missing");
+        let theme = self
+            .resources
+            .get::<Box<dyn MapTheme>>()
+            .expect("Theme is missing")
+            .kind();
+        dungeon.store(map_level, &mut self.ecs, map, theme);
+
+        // The player arrives next to the stairs leading back to where they came from.
+        let arrival_stairs = if delta > 0 {
+            TileType::UpStairs
+        } else {
+            TileType::Exit
+        };
+        let (map, theme) = match dungeon.restore(target_level, &mut self.ecs) {
+            Some((map, theme)) => (map, theme.build()),
+            None => {
+                let player = *<Entity>::query()
+                    .filter(component::<Player>())
+                    .iter(&self.ecs)
+                    .next()
+                    .expect("Player is missing");
+                let level = self.level_loader.load(
+                    target_level,
+                    Some(player),
+                    &mut self.ecs,
+                    &mut self.resources,
+                    &self.config,
+                );
+                (level.map, level.theme)
+            }
+        };
+        let stairs = map
+            .tiles
+            .iter()
+            .position(|t| *t == arrival_stairs)
+            .map(|idx| map.index_to_point2d(idx))
+            .expect("Level has no stairs to arrive at");
+        let player_pos = beside(&map, stairs);
+
+        <(&mut Player, &mut Point)>::query()
+            .iter_mut(&mut self.ecs)
+            .for_each(|(player, pos)| {
+                player.map_level = target_level;
+                *pos = player_pos;
+            });
+        <&mut FieldOfView>::query()
+            .iter_mut(&mut self.ecs)
+            .for_each(|fov| fov.is_dirty = true);
+
+        let text = if delta > 0 {
+            format!("You descend to dungeon level {}.", target_level + 1)
+        } else {
+            format!("You climb back up to dungeon level {}.", target_level + 1)
+        };
+        self.resources
+            .get_mut::<GameLog>()
+            .expect("Game log is missing")
+            .add(text, MAGENTA);
+        let turn = self.resources.get::<TurnCount>().map_or(0, |t| t.0);
+        self.resources
+            .get_mut::<RunStats>()
+            .expect("Run stats are missing")
+            .change_level(map_level, target_level, turn);
+
+        self.enter_level(map, theme, player_pos);
+        self.resources.insert(dungeon);
+    }
+
+    /// Makes `map` the level being played, with the player standing at `player_pos`.
+    fn enter_level(&mut self, map: Map, theme: Box<dyn MapTheme>, player_pos: Point) {
+        self.resources.insert(map);
+        self.resources.insert(Camera::new(player_pos));
+        self.resources.insert(TurnState::AwaitingInput);
+        self.resources.insert(theme);
+        self.resources.insert(LightMap::default());
+    }
+}
+
+/// A tile next to the stairs at `pos` for the player to arrive on, so they don't take
+/// the stairs right back. When every neighbour is blocked, it is the nearest tile they
+/// can walk to instead.
+fn beside(map: &Map, pos: Point) -> Point {
+    let can_arrive = |pt: Point| {
+        map.can_enter_tile(pt)
+            && !matches!(
+                map.tiles[map.point2d_to_index(pt)],
+                TileType::Exit | TileType::UpStairs | TileType::Lava
+            )
+    };
+    if let Some(pt) = DIRECTIONS
+        .iter()
+        .map(|delta| pos + *delta)
+        .find(|pt| can_arrive(*pt))
+    {
+        return pt;
+    }
+
+    let dijkstra_map = DijkstraMap::new(
+        map.width,
+        map.height,
+        &[map.point2d_to_index(pos)],
+        map,
+        1024.0,
+    );
+    let distance = |pt: Point| {
+        (
+            dijkstra_map.map[map.point2d_to_index(pt)],
+            DistanceAlg::Pythagoras.distance2d(pos, pt),
+        )
+    };
+    (0..map.tiles.len())
+        .map(|idx| map.index_to_point2d(idx))
+        .filter(|pt| can_arrive(*pt))
+        .min_by(|a, b| distance(*a).partial_cmp(&distance(*b)).unwrap())
+        .expect("The level has no tile to arrive on")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Replaces the generated level by an open arena with only the player at (10, 10).
+    fn arena(sim: &mut Simulation) -> Entity {
+        sim.ecs = World::default();
+        sim.resources.insert(Map::default());
+        sim.resources.insert(Camera::new(Point::new(10, 10)));
+        sim.resources.insert(TurnState::AwaitingInput);
+        spawn_player(&mut sim.ecs, Point::new(10, 10), &sim.config);
+        *<Entity>::query()
+            .filter(component::<Player>())
+            .iter(&sim.ecs)
+            .next()
+            .unwrap()
+    }
+
+    fn player_pos(sim: &Simulation) -> Point {
+        *<&Point>::query()
+            .filter(component::<Player>())
+            .iter(&sim.ecs)
+            .next()
+            .unwrap()
+    }
+
+    #[test]
+    fn same_seed_and_inputs_play_the_same_game() {
+        let keys = [
+            VirtualKeyCode::Left,
+            VirtualKeyCode::Up,
+            VirtualKeyCode::Up,
+            VirtualKeyCode::Right,
+            VirtualKeyCode::Down,
+        ];
+        let mut sim1 = Simulation::headless(99);
+        let mut sim2 = Simulation::headless(99);
+        sim1.run(&keys);
+        sim2.run(&keys);
+
+        assert_eq!(player_pos(&sim1), player_pos(&sim2));
+        assert_eq!(sim1.ecs.len(), sim2.ecs.len());
+        assert_eq!(sim1.turn_state(), sim2.turn_state());
+    }
+
+    #[test]
+    fn keys_are_translated_through_the_bindings() {
+        let mut sim = Simulation::with_settings(
+            1,
+            false,
+            GameConfig::default(),
+            KeyBindings::from_presets(&[KeyPreset::ViKeys]),
+        );
+        arena(&mut sim);
+
+        assert_eq!(sim.step(VirtualKeyCode::Left), TurnState::AwaitingInput);
+        assert_eq!(player_pos(&sim), Point::new(10, 10));
+        sim.step(VirtualKeyCode::H);
+        assert_eq!(player_pos(&sim), Point::new(9, 10));
+    }
+
+    #[test]
+    fn walk_into_goblin_three_times_and_it_dies() {
+        let mut sim = Simulation::headless(1);
+        arena(&mut sim);
+        let goblin = sim.ecs.push((
+            Enemy,
+            Point::new(11, 10),
+            Name("Goblin".to_string()),
+            Health { current: 3, max: 3 },
+        ));
+
+        sim.step(VirtualKeyCode::Right);
+        sim.step(VirtualKeyCode::Right);
+        assert!(sim.ecs.entry(goblin).is_some());
+        assert_eq!(sim.step(VirtualKeyCode::Right), TurnState::AwaitingInput);
+        assert!(sim.ecs.entry(goblin).is_none());
+        assert_eq!(player_pos(&sim), Point::new(10, 10));
+        let log = sim.resources.get::<GameLog>().unwrap();
+        let last = log.entries().last().unwrap();
+        assert_eq!(last.text, "The Goblin dies.");
+        let stats = sim.resources.get::<RunStats>().unwrap();
+        assert_eq!(stats.kills["Goblin"], 1);
+        assert!(stats.damage_dealt > 0);
+    }
+
+    #[test]
+    fn chasing_monster_kills_the_player() {
+        let mut sim = Simulation::headless(1);
+        let player = arena(&mut sim);
+        sim.ecs.entry(player).unwrap().add_component(Health {
+            current: 1,
+            max: 10,
+        });
+        sim.ecs.push((
+            Enemy,
+            ChasingPlayer,
+            Point::new(12, 10),
+            FieldOfView::new(6),
+            Health { current: 5, max: 5 },
+            Damage(1),
+        ));
+
+        assert_eq!(sim.step(VirtualKeyCode::Right), TurnState::GameOver);
+    }
+
+    #[test]
+    fn monsters_act_as_often_as_their_speed_allows() {
+        let mut sim = Simulation::headless(1);
+        let player = arena(&mut sim);
+        let monster = |pos, speed| {
+            (
+                Enemy,
+                ChasingPlayer,
+                pos,
+                FieldOfView::new(6),
+                Health { current: 5, max: 5 },
+                Damage(1),
+                Speed(speed),
+            )
+        };
+        let player_hp = |sim: &Simulation| {
+            sim.ecs
+                .entry_ref(player)
+                .unwrap()
+                .get_component::<Health>()
+                .unwrap()
+                .current
+        };
+        let goblin = sim.ecs.push(monster(Point::new(11, 10), 2 * NORMAL_SPEED));
+
+        sim.step(VirtualKeyCode::Space);
+        assert_eq!(player_hp(&sim), 8);
+
+        sim.ecs.remove(goblin);
+        sim.ecs.push(monster(Point::new(9, 10), NORMAL_SPEED / 2));
+        sim.step(VirtualKeyCode::Space);
+        assert_eq!(player_hp(&sim), 8);
+        assert_eq!(sim.step(VirtualKeyCode::Space), TurnState::AwaitingInput);
+        assert_eq!(player_hp(&sim), 7);
+    }
+
+    #[test]
+    fn bumping_a_door_opens_it_and_it_can_be_closed_again() {
+        let mut sim = Simulation::headless(1);
+        arena(&mut sim);
+        let mut map = sim.resources.get_mut::<Map>().unwrap();
+        let door_idx = map.map_idx(11, 10);
+        map.tiles[door_idx] = TileType::DoorClosed;
+        drop(map);
+        let tile = |sim: &Simulation| sim.resources.get::<Map>().unwrap().tiles[door_idx];
+
+        sim.step(VirtualKeyCode::Right);
+        assert_eq!(tile(&sim), TileType::DoorOpen);
+        assert_eq!(player_pos(&sim), Point::new(10, 10));
+
+        sim.step(VirtualKeyCode::Right);
+        assert_eq!(player_pos(&sim), Point::new(11, 10));
+        sim.step(VirtualKeyCode::C);
+        assert_eq!(tile(&sim), TileType::DoorOpen);
+
+        sim.step(VirtualKeyCode::Right);
+        sim.step(VirtualKeyCode::C);
+        assert_eq!(tile(&sim), TileType::DoorClosed);
+        let log = sim.resources.get::<GameLog>().unwrap();
+        assert_eq!(log.entries().last().unwrap().text, "You close the door.");
+    }
+
+    fn player_hp(sim: &Simulation) -> i32 {
+        <&Health>::query()
+            .filter(component::<Player>())
+            .iter(&sim.ecs)
+            .next()
+            .unwrap()
+            .current
+    }
+
+    #[test]
+    fn stepping_on_a_hidden_trap_springs_and_reveals_it() {
+        let mut sim = Simulation::headless(1);
+        arena(&mut sim);
+        let start_hp = player_hp(&sim);
+        let trap = sim.ecs.push((
+            Trap,
+            Hidden,
+            Point::new(11, 10),
+            Name("Spike Trap".to_string()),
+            Damage(2),
+        ));
+
+        sim.step(VirtualKeyCode::Right);
+        assert_eq!(player_pos(&sim), Point::new(11, 10));
+        assert_eq!(player_hp(&sim), start_hp - 2);
+        let entry = sim.ecs.entry(trap).unwrap();
+        assert!(entry.get_component::<Hidden>().is_err());
+    }
+
+    #[test]
+    fn poison_wears_off_after_its_turns() {
+        let mut sim = Simulation::headless(1);
+        let player = arena(&mut sim);
+        let start_hp = player_hp(&sim);
+        sim.ecs.push((
+            Trap,
+            Hidden,
+            Point::new(11, 10),
+            Name("Poison Gas Trap".to_string()),
+            Poisons { turns: 2 },
+        ));
+
+        sim.step(VirtualKeyCode::Right);
+        sim.step(VirtualKeyCode::Space);
+        sim.step(VirtualKeyCode::Space);
+        assert_eq!(player_hp(&sim), start_hp - 2);
+        let entry = sim.ecs.entry(player).unwrap();
+        assert!(entry.get_component::<Poisoned>().is_err());
+    }
+
+    fn set_tile(sim: &mut Simulation, pos: Point, tile: TileType) {
+        let mut map = sim.resources.get_mut::<Map>().unwrap();
+        let idx = map.point2d_to_index(pos);
+        map.tiles[idx] = tile;
+    }
+
+    #[test]
+    fn lava_burns_and_deep_water_blocks_the_way() {
+        let mut sim = Simulation::headless(1);
+        arena(&mut sim);
+        let start_hp = player_hp(&sim);
+        set_tile(&mut sim, Point::new(11, 10), TileType::Lava);
+        set_tile(&mut sim, Point::new(12, 10), TileType::DeepWater);
+
+        sim.step(VirtualKeyCode::Right);
+        assert_eq!(player_hp(&sim), start_hp - LAVA_DAMAGE);
+        sim.step(VirtualKeyCode::Right);
+        assert_eq!(player_pos(&sim), Point::new(11, 10));
+    }
+
+    #[test]
+    fn wading_gives_the_monsters_more_time() {
+        let mut sim = Simulation::headless(1);
+        arena(&mut sim);
+        set_tile(&mut sim, Point::new(11, 10), TileType::ShallowWater);
+        let goblin = sim.ecs.push((
+            Enemy,
+            ChasingPlayer,
+            Alerted,
+            Point::new(20, 10),
+            FieldOfView::new(6),
+            Health { current: 1, max: 1 },
+            Name("Goblin".to_string()),
+        ));
+
+        sim.step(VirtualKeyCode::Right);
+        let entry = sim.ecs.entry(goblin).unwrap();
+        assert_eq!(entry.get_component::<Point>(), Ok(&Point::new(18, 10)));
+    }
+
+    #[test]
+    fn unbound_keys_pass_the_turn() {
+        let mut sim = Simulation::headless(1);
+        arena(&mut sim);
+
+        assert_eq!(sim.step(VirtualKeyCode::Q), TurnState::AwaitingInput);
+        assert_eq!(sim.resources.get::<TurnCount>().unwrap().0, 1);
+    }
+
+    #[test]
+    fn scrolling_the_log_does_not_pass_the_turn() {
+        let mut sim = Simulation::headless(1);
+        arena(&mut sim);
+        let mut log = GameLog::default();
+        (0..8).for_each(|n| log.add(n, WHITE));
+        sim.resources.insert(log);
+        let newest = |sim: &Simulation| {
+            let log = sim.resources.get::<GameLog>().unwrap();
+            log.panel_entries().last().unwrap().text.clone()
+        };
+
+        assert_eq!(sim.step(VirtualKeyCode::PageUp), TurnState::AwaitingInput);
+        assert_eq!(newest(&sim), "6");
+        sim.step(VirtualKeyCode::PageDown);
+        assert_eq!(newest(&sim), "7");
+        assert_eq!(sim.resources.get::<TurnCount>().unwrap().0, 0);
+    }
+
+    #[test]
+    fn diagonal_moves_follow_the_game_option() {
+        let mut sim = Simulation::headless(1);
+        arena(&mut sim);
+
+        sim.step(VirtualKeyCode::Home);
+        assert_eq!(player_pos(&sim), Point::new(10, 10));
+        sim.resources.get_mut::<Map>().unwrap().diagonal_movement = true;
+        sim.step(VirtualKeyCode::Home);
+        assert_eq!(player_pos(&sim), Point::new(11, 9));
+    }
+
+    #[test]
+    fn arrow_diagonals_and_log_scrolling_use_their_own_keys() {
+        let mut sim =
+            Simulation::with_settings(1, false, GameConfig::default(), KeyBindings::default());
+        arena(&mut sim);
+        sim.resources.get_mut::<Map>().unwrap().diagonal_movement = true;
+
+        sim.step(VirtualKeyCode::PageUp);
+        sim.step(VirtualKeyCode::PageDown);
+        assert_eq!(player_pos(&sim), Point::new(10, 10));
+        sim.step(VirtualKeyCode::Home);
+        assert_eq!(player_pos(&sim), Point::new(11, 9));
+        sim.step(VirtualKeyCode::End);
+        assert_eq!(player_pos(&sim), Point::new(12, 10));
+        sim.step(VirtualKeyCode::Insert);
+        assert_eq!(player_pos(&sim), Point::new(11, 9));
+        sim.step(VirtualKeyCode::Delete);
+        assert_eq!(player_pos(&sim), Point::new(10, 10));
+    }
+
+    #[test]
+    fn diagonal_moves_cut_no_corners_and_skip_doorways() {
+        let mut sim = Simulation::headless(1);
+        arena(&mut sim);
+        sim.resources.get_mut::<Map>().unwrap().diagonal_movement = true;
+        set_tile(&mut sim, Point::new(11, 10), TileType::Wall);
+        set_tile(&mut sim, Point::new(10, 9), TileType::Wall);
+
+        sim.step(VirtualKeyCode::Home);
+        assert_eq!(player_pos(&sim), Point::new(10, 10));
+        set_tile(&mut sim, Point::new(10, 9), TileType::Floor);
+        sim.step(VirtualKeyCode::Home);
+        assert_eq!(player_pos(&sim), Point::new(11, 9));
+
+        set_tile(&mut sim, Point::new(12, 8), TileType::DoorOpen);
+        sim.step(VirtualKeyCode::Home);
+        assert_eq!(player_pos(&sim), Point::new(11, 9));
+        let map = sim.resources.get::<Map>().unwrap();
+        let from = map.point2d_to_index(Point::new(11, 9));
+        let door = map.point2d_to_index(Point::new(12, 8));
+        assert!(map
+            .get_available_exits(from)
+            .iter()
+            .all(|(idx, _)| *idx != door));
+    }
+
+    #[test]
+    fn a_pickaxe_digs_through_walls_but_not_the_border() {
+        let mut sim = Simulation::headless(1);
+        let player = arena(&mut sim);
+        sim.ecs.push((Item, Digger { turns: 2 }, Carried(player)));
+        set_tile(&mut sim, Point::new(11, 10), TileType::Wall);
+
+        // Using the pickaxe only tells how to dig, so it doesn't take the turn.
+        sim.step(VirtualKeyCode::Key1);
+        assert_eq!(sim.resources.get::<TurnCount>().unwrap().0, 0);
+        sim.step(VirtualKeyCode::Right);
+        assert_eq!(player_pos(&sim), Point::new(10, 10));
+        sim.step(VirtualKeyCode::Right);
+        let map = sim.resources.get::<Map>().unwrap();
+        assert_eq!(map.tiles[map.map_idx(11, 10)], TileType::Floor);
+        assert!(!map.can_dig(Point::new(0, 10)));
+    }
+
+    #[test]
+    fn searching_finds_the_secret_doors_next_to_the_player() {
+        let mut sim = Simulation::headless(1);
+        arena(&mut sim);
+        set_tile(&mut sim, Point::new(11, 10), TileType::DoorSecret);
+        set_tile(&mut sim, Point::new(13, 10), TileType::DoorSecret);
+
+        sim.step(VirtualKeyCode::Right);
+        assert_eq!(player_pos(&sim), Point::new(10, 10));
+        assert_eq!(sim.step(VirtualKeyCode::F), TurnState::AwaitingInput);
+        let map = sim.resources.get::<Map>().unwrap();
+        assert_eq!(map.tiles[map.map_idx(11, 10)], TileType::DoorClosed);
+        assert_eq!(map.tiles[map.map_idx(13, 10)], TileType::DoorSecret);
+    }
+
+    #[test]
+    fn explosives_blast_the_walls_around() {
+        let mut sim = Simulation::headless(1);
+        let player = arena(&mut sim);
+        sim.ecs
+            .push((Item, Explodes { radius: 2 }, Carried(player)));
+        set_tile(&mut sim, Point::new(12, 10), TileType::Wall);
+        set_tile(&mut sim, Point::new(14, 10), TileType::Wall);
+
+        sim.step(VirtualKeyCode::Key1);
+        let map = sim.resources.get::<Map>().unwrap();
+        assert_eq!(map.tiles[map.map_idx(12, 10)], TileType::Rubble);
+        assert_eq!(map.tiles[map.map_idx(14, 10)], TileType::Wall);
+    }
+
+    #[test]
+    fn taking_the_exit_loads_the_next_level() {
+        let mut sim = Simulation::headless(1);
+        arena(&mut sim);
+        let mut map = sim.resources.get_mut::<Map>().unwrap();
+        let exit_idx = map.map_idx(11, 10);
+        map.tiles[exit_idx] = TileType::Exit;
+        drop(map);
+
+        assert_eq!(sim.step(VirtualKeyCode::Right), TurnState::AwaitingInput);
+        let map_level = <&Player>::query().iter(&sim.ecs).next().unwrap().map_level;
+        assert_eq!(map_level, 1);
+    }
+
+    #[test]
+    fn arriving_next_to_blocked_stairs_never_lands_on_them() {
+        let mut map = Map::default();
+        let stairs = Point::new(10, 10);
+        DIRECTIONS.iter().for_each(|delta| {
+            let idx = map.point2d_to_index(stairs + *delta);
+            map.tiles[idx] = TileType::Wall;
+        });
+        let door = map.point2d_to_index(Point::new(11, 10));
+        map.tiles[door] = TileType::DoorClosed;
+        let stairs_idx = map.point2d_to_index(stairs);
+        map.tiles[stairs_idx] = TileType::UpStairs;
+
+        assert_eq!(beside(&map, stairs), Point::new(12, 10));
+        map.tiles[door] = TileType::ShallowWater;
+        assert_eq!(beside(&map, stairs), Point::new(11, 10));
+    }
+
+    #[test]
+    fn going_back_up_finds_the_level_as_it_was_left() {
+        let mut sim = Simulation::headless(1);
+        arena(&mut sim);
+        let goblin = sim.ecs.push((Enemy, Point::new(15, 15)));
+        let mut map = sim.resources.get_mut::<Map>().unwrap();
+        let exit_idx = map.map_idx(11, 10);
+        map.tiles[exit_idx] = TileType::Exit;
+        map.revealed_tiles[exit_idx] = true;
+        drop(map);
+
+        sim.step(VirtualKeyCode::Right);
+        assert!(sim.ecs.entry(goblin).is_none());
+        let map = sim.resources.get::<Map>().unwrap();
+        let up_stairs = map.index_to_point2d(
+            map.tiles
+                .iter()
+                .position(|t| *t == TileType::UpStairs)
+                .unwrap(),
+        );
+        drop(map);
+
+        // Stand on the up stairs and wait a turn.
+        <&mut Point>::query()
+            .filter(component::<Player>())
+            .for_each_mut(&mut sim.ecs, |pos| *pos = up_stairs);
+        assert_eq!(sim.step(VirtualKeyCode::Space), TurnState::AwaitingInput);
+
+        let map_level = <&Player>::query().iter(&sim.ecs).next().unwrap().map_level;
+        assert_eq!(map_level, 0);
+        assert_eq!(
+            sim.ecs.entry(goblin).unwrap().get_component::<Point>(),
+            Ok(&Point::new(15, 15))
+        );
+        assert!(sim.resources.get::<Map>().unwrap().revealed_tiles[exit_idx]);
+        assert_eq!(
+            DistanceAlg::Manhattan.distance2d(player_pos(&sim), Point::new(11, 10)),
+            1.0
+        );
+    }
+
+    fn with_victory(victory: VictoryCondition) -> Simulation {
+        let config = GameConfig {
+            victory,
+            ..Default::default()
+        };
+        Simulation::with_settings(1, false, config, KeyBindings::default())
+    }
+
+    #[test]
+    fn surviving_enough_turns_wins() {
+        let mut sim = with_victory(VictoryCondition::Survive { turns: 3 });
+        arena(&mut sim);
+
+        assert_eq!(sim.step(VirtualKeyCode::Space), TurnState::AwaitingInput);
+        assert_eq!(sim.step(VirtualKeyCode::Space), TurnState::AwaitingInput);
+        assert_eq!(sim.step(VirtualKeyCode::Space), TurnState::Victory);
+    }
+
+    #[test]
+    fn slaying_the_boss_wins() {
+        let mut sim = with_victory(VictoryCondition::SlayBoss {
+            boss: "Goblin".to_string(),
+            depth: 0,
+        });
+        arena(&mut sim);
+        sim.ecs.push((
+            Enemy,
+            Boss,
+            Point::new(11, 10),
+            Health { current: 1, max: 1 },
+        ));
+
+        assert_eq!(sim.step(VirtualKeyCode::Right), TurnState::Victory);
+    }
+
+    #[test]
+    fn carrying_the_artifact_to_the_surface_wins() {
+        let mut sim = with_victory(VictoryCondition::ReturnToSurface {
+            artifact: "Amulet of Yala".to_string(),
+            depth: 2,
+        });
+        let player = arena(&mut sim);
+        spawn_amulet_of_yala(&mut sim.ecs, Point::new(10, 11));
+
+        // Reaching the artifact is not enough, it must be picked up.
+        assert_eq!(sim.step(VirtualKeyCode::Down), TurnState::AwaitingInput);
+        assert_eq!(sim.step(VirtualKeyCode::G), TurnState::Victory);
+        assert!(<&Carried>::query()
+            .iter(&sim.ecs)
+            .any(|carried| carried.0 == player));
+    }
+
+    #[test]
+    fn picking_up_the_amulet_wins() {
+        let mut sim = Simulation::headless(1);
+        arena(&mut sim);
+        spawn_amulet_of_yala(&mut sim.ecs, Point::new(10, 11));
+
+        assert_eq!(sim.step(VirtualKeyCode::Down), TurnState::Victory);
+    }
+
+    #[test]
+    fn continued_games_keep_the_config_they_started_with() {
+        let sim = with_victory(VictoryCondition::Survive { turns: 3 });
+        let save = serialize_game(&sim.ecs, &sim.resources, sim.seed).unwrap();
+        let (ecs, resources, seed) = deserialize_game(&save).unwrap();
+        let continued = Simulation::from_world(seed, ecs, resources).unwrap();
+
+        assert_eq!(continued.config, sim.config);
+        assert_eq!(
+            *continued.resources.get::<GameConfig>().unwrap(),
+            sim.config
+        );
+    }
+}
@@ -4,7 +4,17 @@ use crate::prelude::*;
 
 use self::template::Templates;
 
-pub fn spawn_player(ecs: &mut World, pos: Point) {
+/// The lantern of the player, which only lights the tiles close to them.
+const PLAYER_LIGHT: LightSource = LightSource {
+    radius: 5,
+    color: RGB {
+        r: 1.0,
+        g: 0.85,
+        b: 0.6,
+    },
+};
+
+pub fn spawn_player(ecs: &mut World, pos: Point, config: &GameConfig) {
     ecs.push((
         Player { map_level: 0 },
         pos,
@@ -13,15 +23,20 @@ pub fn spawn_player(ecs: &mut World, pos: Point) {
             glyph: to_cp437('@'),
         },
         Health {
-            current: 10,
-            max: 10,
+            current: config.player_hp,
+            max: config.player_hp,
         },
-        FieldOfView::new(8),
+        FieldOfView::new(config.player_fov),
         Damage(1),
+        PLAYER_LIGHT,
     ));
 }
 
 pub fn spawn_amulet_of_yala(ecs: &mut World, pos: Point) {
+    spawn_artifact(ecs, pos, "Amulet of Yala");
+}
+
+pub fn spawn_artifact(ecs: &mut World, pos: Point, name: &str) {
     ecs.push((
         Item,
         AmuletOfYala,
@@ -30,19 +45,54 @@ pub fn spawn_amulet_of_yala(ecs: &mut World, pos: Point) {
             color: ColorPair::new(WHITE, BLACK),
             glyph: to_cp437('|'),
         },
-        Name("Amulet of Yala".to_string()),
+        Name(name.to_string()),
     ));
 }
 
+/// Spawns the artifact or the boss of the victory condition, if it has one.
+pub fn spawn_victory_goal(
+    ecs: &mut World,
+    resources: &mut Resources,
+    pos: Point,
+    config: &GameConfig,
+) {
+    match &config.victory {
+        VictoryCondition::RetrieveArtifact { artifact, .. }
+        | VictoryCondition::ReturnToSurface { artifact, .. } => spawn_artifact(ecs, pos, artifact),
+        VictoryCondition::SlayBoss { boss, .. } => {
+            Templates::load().spawn_boss(ecs, resources, boss, pos, config)
+        }
+        VictoryCondition::Survive { .. } => {}
+    }
+}
+
+/// Whether `resources/template.ron` has an enemy named `name`.
+pub fn is_enemy_template(name: &str) -> bool {
+    Templates::load().enemy(name).is_some()
+}
+
 pub fn spawn_level(
     ecs: &mut World,
     resources: &mut Resources,
     rng: &mut RandomNumberGenerator,
     level: usize,
     spawn_points: &[Point],
+    config: &GameConfig,
+) {
+    let template = Templates::load();
+    template.spawn_entities(ecs, resources, rng, level, spawn_points, config);
+}
+
+pub fn spawn_level_traps(
+    ecs: &mut World,
+    resources: &mut Resources,
+    rng: &mut RandomNumberGenerator,
+    level: usize,
+    trap_points: &[Point],
+    config: &GameConfig,
 ) {
     let template = Templates::load();
-    template.spawn_entities(ecs, resources, rng, level, spawn_points);
+    template.spawn_traps(ecs, resources, rng, level, trap_points, config);
 }
 
 #[cfg(test)]
@@ -54,7 +104,7 @@ mod tests {
         let mut ecs = World::default();
         let pos = Point::new(10, 10);
 
-        spawn_player(&mut ecs, pos);
+        spawn_player(&mut ecs, pos, &GameConfig::default());
         assert_eq!(ecs.len(), 1);
 
         let player_entity = <Entity>::query().iter(&ecs).next().unwrap();
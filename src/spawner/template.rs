@@ -16,12 +16,17 @@ pub struct Template {
     pub provides: Option<Vec<(String, i32)>>,
     pub hp: Option<i32>,
     pub base_damage: Option<i32>,
+    /// The energy an enemy gains per turn, `NORMAL_SPEED` if not set.
+    pub speed: Option<i32>,
+    /// The radius and color of the light given off by the entity, if any.
+    pub light: Option<(i32, (u8, u8, u8))>,
 }
 
 #[derive(Clone, Deserialize, Debug, PartialEq)]
 pub enum EntityType {
     Enemy,
     Item,
+    Trap,
 }
 
 #[derive(Clone, Deserialize, Debug)]
@@ -35,6 +40,7 @@ impl Templates {
         from_reader(file).expect("Unable to load templates")
     }
 
+    /// Spawns a monster or an item of `level` on each of the spawn points.
     pub fn spawn_entities(
         &self,
         ecs: &mut World,
@@ -42,11 +48,45 @@ impl Templates {
         rng: &mut RandomNumberGenerator,
         level: usize,
         spawn_points: &[Point],
+        config: &GameConfig,
+    ) {
+        self.spawn_weighted(ecs, resources, rng, level, spawn_points, config, |t| {
+            t.entity_type != EntityType::Trap
+        });
+    }
+
+    /// Spawns a trap of `level` on each of the trap points.
+    pub fn spawn_traps(
+        &self,
+        ecs: &mut World,
+        resources: &mut Resources,
+        rng: &mut RandomNumberGenerator,
+        level: usize,
+        trap_points: &[Point],
+        config: &GameConfig,
+    ) {
+        self.spawn_weighted(ecs, resources, rng, level, trap_points, config, |t| {
+            t.entity_type == EntityType::Trap
+        });
+    }
+
+    /// Spawns on each point one of the templates of `level` kept by `filter`, picked by
+    /// their frequency.
+    #[allow(clippy::too_many_arguments)]
+    fn spawn_weighted(
+        &self,
+        ecs: &mut World,
+        resources: &mut Resources,
+        rng: &mut RandomNumberGenerator,
+        level: usize,
+        points: &[Point],
+        config: &GameConfig,
+        filter: impl Fn(&Template) -> bool,
     ) {
         let mut available_entities = Vec::new();
         self.entities
             .iter()
-            .filter(|e| e.levels.contains(&level))
+            .filter(|e| e.levels.contains(&level) && filter(e))
             .for_each(|t| {
                 for _ in 0..t.frequency {
                     available_entities.push(t);
@@ -54,15 +94,45 @@ impl Templates {
             });
 
         let mut commands = CommandBuffer::new(ecs);
-        spawn_points.iter().for_each(|pt| {
+        points.iter().for_each(|pt| {
             if let Some(entity) = rng.random_slice_entry(&available_entities) {
-                self.spawn_entity(pt, entity, &mut commands);
+                self.spawn_entity(pt, entity, &mut commands, config);
             }
         });
         commands.flush(ecs, resources);
     }
 
-    fn spawn_entity(&self, pt: &Point, template: &Template, commands: &mut CommandBuffer) {
+    /// Finds the enemy named `name`.
+    pub fn enemy(&self, name: &str) -> Option<&Template> {
+        self.entities
+            .iter()
+            .find(|t| t.entity_type == EntityType::Enemy && t.name == name)
+    }
+
+    /// Spawns the enemy named `name` as the boss of the level.
+    pub fn spawn_boss(
+        &self,
+        ecs: &mut World,
+        resources: &mut Resources,
+        name: &str,
+        pos: Point,
+        config: &GameConfig,
+    ) {
+        let template = self.enemy(name).expect("Unknown boss template");
+
+        let mut commands = CommandBuffer::new(ecs);
+        let boss = self.spawn_entity(&pos, template, &mut commands, config);
+        commands.add_component(boss, Boss);
+        commands.flush(ecs, resources);
+    }
+
+    fn spawn_entity(
+        &self,
+        pt: &Point,
+        template: &Template,
+        commands: &mut CommandBuffer,
+        config: &GameConfig,
+    ) -> Entity {
         let entity = commands.push((
             *pt,
             Render {
@@ -74,10 +144,15 @@ impl Templates {
 
         match template.entity_type {
             EntityType::Item => commands.add_component(entity, Item {}),
+            EntityType::Trap => {
+                commands.add_component(entity, Trap);
+                commands.add_component(entity, Hidden);
+            }
             EntityType::Enemy => {
                 commands.add_component(entity, Enemy {});
-                commands.add_component(entity, FieldOfView::new(6));
+                commands.add_component(entity, FieldOfView::new(config.enemy_fov));
                 commands.add_component(entity, ChasingPlayer {});
+                commands.add_component(entity, Speed(template.speed.unwrap_or(NORMAL_SPEED)));
                 commands.add_component(
                     entity,
                     Health {
@@ -94,15 +169,30 @@ impl Templates {
                 .for_each(|(provides, n)| match provides.as_str() {
                     "Healing" => commands.add_component(entity, ProvidesHealing { amount: *n }),
                     "MagicMap" => commands.add_component(entity, ProvidesDungeonMap {}),
+                    "Teleport" => commands.add_component(entity, Teleports),
+                    "Alarm" => commands.add_component(entity, SoundsAlarm),
+                    "Poison" => commands.add_component(entity, Poisons { turns: *n }),
+                    "Digging" => commands.add_component(entity, Digger { turns: *n }),
+                    "Explosion" => commands.add_component(entity, Explodes { radius: *n }),
                     _ => println!("Warning: Unknown provider for {}", provides),
                 });
         }
+        if let Some((radius, color)) = template.light {
+            commands.add_component(
+                entity,
+                LightSource {
+                    radius,
+                    color: color.into(),
+                },
+            );
+        }
         if let Some(damage) = &template.base_damage {
             commands.add_component(entity, Damage(*damage));
             if template.entity_type == EntityType::Item {
                 commands.add_component(entity, Weapon {});
             }
         }
+        entity
     }
 }
 
@@ -112,8 +202,8 @@ mod tests {
 
     fn build_test_item(frequency: i32) -> Template {
         let mut levels = HashSet::new();
-        levels.insert(2 as usize);
-        levels.insert(3 as usize);
+        levels.insert(2_usize);
+        levels.insert(3_usize);
 
         Template {
             entity_type: EntityType::Item,
@@ -124,13 +214,15 @@ mod tests {
             provides: None,
             hp: None,
             base_damage: None,
+            speed: None,
+            light: None,
         }
     }
 
     fn build_test_enemy(frequency: i32) -> Template {
         let mut levels = HashSet::new();
-        levels.insert(2 as usize);
-        levels.insert(3 as usize);
+        levels.insert(2_usize);
+        levels.insert(3_usize);
 
         Template {
             entity_type: EntityType::Enemy,
@@ -141,6 +233,8 @@ mod tests {
             provides: None,
             hp: Some(5),
             base_damage: Some(2),
+            speed: None,
+            light: None,
         }
     }
 
@@ -155,7 +249,14 @@ mod tests {
         };
         let spawn = [];
 
-        templates.spawn_entities(&mut ecs, &mut resources, &mut rng, 2 as usize, &spawn);
+        templates.spawn_entities(
+            &mut ecs,
+            &mut resources,
+            &mut rng,
+            2_usize,
+            &spawn,
+            &GameConfig::default(),
+        );
         assert_eq!(ecs.len(), 0);
     }
 
@@ -170,7 +271,14 @@ mod tests {
         };
         let spawn = [Point::new(10, 10)];
 
-        templates.spawn_entities(&mut ecs, &mut resources, &mut rng, 1 as usize, &spawn);
+        templates.spawn_entities(
+            &mut ecs,
+            &mut resources,
+            &mut rng,
+            1_usize,
+            &spawn,
+            &GameConfig::default(),
+        );
         assert_eq!(ecs.len(), 0);
     }
 
@@ -185,7 +293,14 @@ mod tests {
         };
         let spawn = [Point::new(10, 10)];
 
-        templates.spawn_entities(&mut ecs, &mut resources, &mut rng, 2 as usize, &spawn);
+        templates.spawn_entities(
+            &mut ecs,
+            &mut resources,
+            &mut rng,
+            2_usize,
+            &spawn,
+            &GameConfig::default(),
+        );
         assert_eq!(ecs.len(), 0);
     }
 
@@ -200,7 +315,14 @@ mod tests {
         };
         let spawn = [Point::new(10, 10)];
 
-        templates.spawn_entities(&mut ecs, &mut resources, &mut rng, 2 as usize, &spawn);
+        templates.spawn_entities(
+            &mut ecs,
+            &mut resources,
+            &mut rng,
+            2_usize,
+            &spawn,
+            &GameConfig::default(),
+        );
         assert_eq!(ecs.len(), 1);
     }
 
@@ -215,7 +337,14 @@ mod tests {
         };
         let spawn = [Point::new(10, 10)];
 
-        templates.spawn_entities(&mut ecs, &mut resources, &mut rng, 2 as usize, &spawn);
+        templates.spawn_entities(
+            &mut ecs,
+            &mut resources,
+            &mut rng,
+            2_usize,
+            &spawn,
+            &GameConfig::default(),
+        );
         assert_eq!(ecs.len(), 1);
     }
 }
@@ -1,4 +1,52 @@
 use crate::prelude::*;
+use std::collections::HashSet;
+
+/// The map as the monsters see it: they walk around the traps the player has revealed,
+/// and the burrowers also go through the walls, which take them `dig_turns` to dig.
+struct MonsterMap<'a> {
+    map: &'a Map,
+    traps: &'a HashSet<usize>,
+    dig_turns: Option<i32>,
+}
+
+impl Algorithm2D for MonsterMap<'_> {
+    fn dimensions(&self) -> Point {
+        self.map.dimensions()
+    }
+
+    fn in_bounds(&self, pos: Point) -> bool {
+        self.map.in_bounds(pos)
+    }
+}
+
+impl BaseMap for MonsterMap<'_> {
+    fn get_available_exits(&self, idx: usize) -> SmallVec<[(usize, f32); 10]> {
+        let mut exits: SmallVec<[(usize, f32); 10]> = self
+            .map
+            .get_available_exits(idx)
+            .into_iter()
+            .filter(|(exit, _)| !self.traps.contains(exit))
+            .collect();
+        if let Some(dig_turns) = self.dig_turns {
+            let location = self.map.index_to_point2d(idx);
+            self.map
+                .directions()
+                .iter()
+                .filter(|delta| {
+                    self.map.can_dig(location + **delta) && self.map.can_step(location, **delta)
+                })
+                .for_each(|delta| {
+                    let wall = self.map.point2d_to_index(location + *delta);
+                    exits.push((wall, (dig_turns + 1) as f32 * step_length(*delta)));
+                });
+        }
+        exits
+    }
+
+    fn get_pathing_distance(&self, idx1: usize, idx2: usize) -> f32 {
+        self.map.get_pathing_distance(idx1, idx2)
+    }
+}
 
 #[system]
 #[read_component(Point)]
@@ -6,61 +54,118 @@ use crate::prelude::*;
 #[read_component(FieldOfView)]
 #[read_component(Health)]
 #[read_component(Player)]
+#[read_component(Energy)]
+#[read_component(Alerted)]
+#[read_component(Trap)]
+#[read_component(Hidden)]
+#[read_component(Digger)]
 pub fn chasing(ecs: &SubWorld, commands: &mut CommandBuffer, #[resource] map: &Map) {
-    let mut movers = <(Entity, &Point, &ChasingPlayer, &FieldOfView)>::query();
+    let mut movers = <(
+        Entity,
+        &Point,
+        &ChasingPlayer,
+        &FieldOfView,
+        &Energy,
+        Option<&Alerted>,
+        Option<&Digger>,
+    )>::query();
     let mut positions = <(Entity, &Point, &Health)>::query();
     let mut player = <(&Point, &Player)>::query();
 
     let player_pos = player.iter(ecs).next().unwrap().0;
-    let player_idx = map_idx(player_pos.x, player_pos.y);
+    let player_idx = map.map_idx(player_pos.x, player_pos.y);
 
+    let traps: HashSet<usize> = <&Point>::query()
+        .filter(component::<Trap>() & !component::<Hidden>())
+        .iter(ecs)
+        .map(|pos| map.point2d_to_index(*pos))
+        .filter(|idx| *idx != player_idx)
+        .collect();
     let search_targets = vec![player_idx];
-    let dijkstra_map = DijkstraMap::new(SCREEN_WIDTH, SCREEN_HEIGHT, &search_targets, map, 1024.0);
+    let walking_map = MonsterMap {
+        map,
+        traps: &traps,
+        dig_turns: None,
+    };
+    let walking_dijkstra =
+        DijkstraMap::new(map.width, map.height, &search_targets, &walking_map, 1024.0);
+    // The burrowers only get their own paths when there are some around.
+    let burrowing_map = movers
+        .iter(ecs)
+        .filter_map(|(_, _, _, _, _, _, digger)| digger.map(|digger| digger.turns))
+        .min()
+        .map(|dig_turns| MonsterMap {
+            map,
+            traps: &traps,
+            dig_turns: Some(dig_turns),
+        });
+    let burrowing_dijkstra = burrowing_map.as_ref().map(|burrowing_map| {
+        DijkstraMap::new(
+            map.width,
+            map.height,
+            &search_targets,
+            burrowing_map,
+            1024.0,
+        )
+    });
 
-    movers.iter(ecs).for_each(|(entity, pos, _, fov)| {
-        if !fov.visible_tiles.contains(player_pos) {
-            return;
-        }
-        let idx = map_idx(pos.x, pos.y);
-        if let Some(destination) = DijkstraMap::find_lowest_exit(&dijkstra_map, idx, map) {
-            let distance = DistanceAlg::Pythagoras.distance2d(*pos, *player_pos);
-            let destination = if distance > 1.2 {
-                map.index_to_point2d(destination)
-            } else {
-                *player_pos
+    movers
+        .iter(ecs)
+        .for_each(|(entity, pos, _, fov, energy, alerted, digger)| {
+            if energy.0 < ACTION_COST {
+                return;
+            }
+            commands.add_component(*entity, Energy(energy.0 - ACTION_COST));
+            // An alarm tells the monsters where the player is, seen or not.
+            if alerted.is_none() && !fov.visible_tiles.contains(player_pos) {
+                return;
+            }
+            let idx = map.map_idx(pos.x, pos.y);
+            let lowest_exit = match (digger, &burrowing_map, &burrowing_dijkstra) {
+                (Some(_), Some(burrowing_map), Some(burrowing_dijkstra)) => {
+                    DijkstraMap::find_lowest_exit(burrowing_dijkstra, idx, burrowing_map)
+                }
+                _ => DijkstraMap::find_lowest_exit(&walking_dijkstra, idx, &walking_map),
             };
+            if let Some(destination) = lowest_exit {
+                let distance = DistanceAlg::Pythagoras.distance2d(*pos, *player_pos);
+                let destination = if distance > 1.2 {
+                    map.index_to_point2d(destination)
+                } else {
+                    *player_pos
+                };
 
-            let mut attacked = false;
-            positions
-                .iter(ecs)
-                .filter(|(_, target_pos, _)| **target_pos == destination)
-                .for_each(|(victim, _, _)| {
-                    if ecs
-                        .entry_ref(*victim)
-                        .unwrap()
-                        .get_component::<Player>()
-                        .is_ok()
-                    {
-                        commands.push((
-                            (),
-                            WantsToAttack {
-                                attacker: *entity,
-                                victim: *victim,
-                            },
-                        ));
-                    }
-                    attacked = true;
-                });
-            if !attacked {
-                commands.push((
-                    (),
-                    WantsToMove {
-                        entity: *entity,
-                        from: *pos,
-                        destination,
-                    },
-                ));
+                let mut attacked = false;
+                positions
+                    .iter(ecs)
+                    .filter(|(_, target_pos, _)| **target_pos == destination)
+                    .for_each(|(victim, _, _)| {
+                        if ecs
+                            .entry_ref(*victim)
+                            .unwrap()
+                            .get_component::<Player>()
+                            .is_ok()
+                        {
+                            commands.push((
+                                (),
+                                WantsToAttack {
+                                    attacker: *entity,
+                                    victim: *victim,
+                                },
+                            ));
+                        }
+                        attacked = true;
+                    });
+                if !attacked {
+                    commands.push((
+                        (),
+                        WantsToMove {
+                            entity: *entity,
+                            from: *pos,
+                            destination,
+                        },
+                    ));
+                }
             }
-        }
-    });
+        });
 }
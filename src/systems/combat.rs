@@ -5,8 +5,14 @@ use crate::prelude::*;
 #[read_component(Player)]
 #[read_component(Damage)]
 #[read_component(Carried)]
+#[read_component(Name)]
 #[write_component(Health)]
-pub fn combat(ecs: &mut SubWorld, commands: &mut CommandBuffer) {
+pub fn combat(
+    ecs: &mut SubWorld,
+    commands: &mut CommandBuffer,
+    #[resource] log: &mut GameLog,
+    #[resource] stats: &mut RunStats,
+) {
     let mut attackers = <(Entity, &WantsToAttack)>::query();
 
     let victms: Vec<(Entity, Entity, Entity)> = attackers
@@ -38,17 +44,67 @@ pub fn combat(ecs: &mut SubWorld, commands: &mut CommandBuffer) {
             .sum();
 
         let final_damage = base_damage + weapon_damage;
+        let attacker_is_player = ecs
+            .entry_ref(*attacker)
+            .is_ok_and(|entry| entry.get_component::<Player>().is_ok());
+        let attacker_name = name_of(ecs, *attacker);
+        let victim_name = name_of(ecs, *victim);
 
-        if let Ok(mut health) = ecs
+        if let Ok(health) = ecs
             .entry_mut(*victim)
             .unwrap()
             .get_component_mut::<Health>()
         {
             health.current -= final_damage;
-            if health.current < 1 && !is_player {
-                commands.remove(*victim);
+            if attacker_is_player {
+                stats.damage_dealt += final_damage.max(0) as u32;
+            } else if is_player {
+                stats.damage_taken += final_damage.max(0) as u32;
+            }
+            if attacker_is_player {
+                log.add(
+                    format!("You hit the {} for {}.", victim_name, final_damage),
+                    WHITE,
+                );
+            } else if is_player {
+                log.add(
+                    format!("The {} hits you for {}.", attacker_name, final_damage),
+                    RED,
+                );
+            } else {
+                log.add(
+                    format!(
+                        "The {} hits the {} for {}.",
+                        attacker_name, victim_name, final_damage
+                    ),
+                    WHITE,
+                );
+            }
+            if health.current < 1 {
+                if is_player {
+                    log.add("You die...", RED);
+                    commands.add_component(*victim, SlainBy::Monster(attacker_name.clone()));
+                } else {
+                    log.add(format!("The {} dies.", victim_name), YELLOW);
+                    if attacker_is_player {
+                        stats.add_kill(&victim_name);
+                    }
+                    commands.remove(*victim);
+                }
             }
         }
         commands.remove(*message);
     });
 }
+
+fn name_of(ecs: &SubWorld, entity: Entity) -> String {
+    ecs.entry_ref(entity)
+        .ok()
+        .and_then(|entry| {
+            entry
+                .get_component::<Name>()
+                .ok()
+                .map(|name| name.0.clone())
+        })
+        .unwrap_or_else(|| "something".to_string())
+}
@@ -0,0 +1,61 @@
+use super::movement::{is_player, player_sees, refresh_views};
+use crate::prelude::*;
+
+/// Digs `digger` one turn further into the wall at `target`, if it can dig at all. It
+/// breaks through after as many turns in a row as its fastest digging tool needs.
+pub(super) fn dig(
+    ecs: &SubWorld,
+    commands: &mut CommandBuffer,
+    map: &mut Map,
+    log: &mut GameLog,
+    digger: Entity,
+    target: Point,
+) {
+    let Some(turns) = dig_turns(ecs, digger) else {
+        return;
+    };
+    let Ok(entry) = ecs.entry_ref(digger) else {
+        return;
+    };
+    let progress = entry
+        .get_component::<Digging>()
+        .ok()
+        .filter(|digging| digging.target == target)
+        .map_or(0, |digging| digging.progress)
+        + 1;
+    if progress < turns {
+        commands.add_component(digger, Digging { target, progress });
+        if is_player(ecs, digger) {
+            log.add("You dig at the wall.", WHITE);
+        }
+        return;
+    }
+
+    commands.remove_component::<Digging>(digger);
+    let idx = map.point2d_to_index(target);
+    map.tiles[idx] = TileType::Floor;
+    if is_player(ecs, digger) {
+        log.add("You dig through the wall.", WHITE);
+    } else if player_sees(ecs, target) {
+        let name = entry
+            .get_component::<Name>()
+            .map(|name| name.0.clone())
+            .unwrap_or_else(|_| "something".to_string());
+        log.add(format!("The {} bursts through the wall!", name), ORANGE);
+    }
+    refresh_views(ecs, commands);
+}
+
+/// How many turns `entity` takes to dig through a wall, if it can at all.
+fn dig_turns(ecs: &SubWorld, entity: Entity) -> Option<i32> {
+    let own = ecs
+        .entry_ref(entity)
+        .ok()
+        .and_then(|entry| entry.get_component::<Digger>().ok().map(|d| d.turns));
+    let carried = <(&Carried, &Digger)>::query()
+        .iter(ecs)
+        .filter(|(carried, _)| carried.0 == entity)
+        .map(|(_, digger)| digger.turns)
+        .min();
+    own.into_iter().chain(carried).min()
+}
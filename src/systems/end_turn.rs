@@ -1,13 +1,30 @@
 use crate::prelude::*;
 
 /// This systems performs end of turns checks, in order to set the next state in the state machine.
+///
+/// At the end of the player's turn every monster gains the energy given by its speed. The
+/// monster turn is then repeated for as long as some monster still has the energy to act, so
+/// fast monsters can act several times per turn and slow ones only every few turns.
 #[system]
 #[read_component(Health)]
 #[read_component(Point)]
 #[read_component(Player)]
 #[read_component(AmuletOfYala)]
-pub fn end_turn(ecs: &SubWorld, #[resource] turn_state: &mut TurnState, #[resource] map: &Map) {
-    let mut player_hp = <(&Health, &Point)>::query().filter(component::<Player>());
+#[read_component(Carried)]
+#[read_component(Boss)]
+#[read_component(ChasingPlayer)]
+#[read_component(MovingRandomly)]
+#[read_component(Speed)]
+#[read_component(Energy)]
+pub fn end_turn(
+    ecs: &SubWorld,
+    commands: &mut CommandBuffer,
+    #[resource] turn_state: &mut TurnState,
+    #[resource] turn_count: &mut TurnCount,
+    #[resource] map: &Map,
+    #[resource] config: &GameConfig,
+) {
+    let mut player_hp = <(&Player, &Health, &Point)>::query();
     let mut amulet = <&Point>::query().filter(component::<AmuletOfYala>());
 
     let amulet_default = Point::new(-1, -1);
@@ -16,21 +33,59 @@ pub fn end_turn(ecs: &SubWorld, #[resource] turn_state: &mut TurnState, #[resour
     let current_state = *turn_state;
     let mut new_state = match current_state {
         TurnState::AwaitingInput => return,
-        TurnState::PlayerTurn => TurnState::MonsterTurn,
-        TurnState::MonsterTurn => TurnState::AwaitingInput,
+        TurnState::PlayerTurn => {
+            <(Entity, Option<&Speed>, Option<&Energy>)>::query()
+                .filter(component::<ChasingPlayer>() | component::<MovingRandomly>())
+                .for_each(ecs, |(entity, speed, energy)| {
+                    let speed = speed.map_or(NORMAL_SPEED, |s| s.0);
+                    let energy = energy.map_or(0, |e| e.0);
+                    commands.add_component(*entity, Energy(energy + speed));
+                });
+            TurnState::MonsterTurn
+        }
+        TurnState::MonsterTurn
+            if <&Energy>::query()
+                .filter(component::<ChasingPlayer>() | component::<MovingRandomly>())
+                .iter(ecs)
+                .any(|energy| energy.0 >= ACTION_COST) =>
+        {
+            TurnState::MonsterTurn
+        }
+        TurnState::MonsterTurn => {
+            turn_count.0 += 1;
+            TurnState::AwaitingInput
+        }
         _ => current_state,
     };
 
-    player_hp.iter(ecs).for_each(|(hp, pos)| {
+    player_hp.iter(ecs).for_each(|(player, hp, pos)| {
         if hp.current < 1 {
             new_state = TurnState::GameOver;
         }
-        if pos == amulet_pos {
+        let won = match &config.victory {
+            VictoryCondition::RetrieveArtifact { .. } => pos == amulet_pos,
+            VictoryCondition::ReturnToSurface { .. } => {
+                player.map_level == 0
+                    && <&Carried>::query()
+                        .filter(component::<AmuletOfYala>())
+                        .iter(ecs)
+                        .next()
+                        .is_some()
+            }
+            // The boss is spawned with its level, so if it's gone from there it is dead.
+            VictoryCondition::SlayBoss { depth, .. } => {
+                player.map_level == *depth && <&Boss>::query().iter(ecs).next().is_none()
+            }
+            VictoryCondition::Survive { turns } => turn_count.0 >= *turns,
+        };
+        if won && hp.current > 0 {
             new_state = TurnState::Victory;
         }
         let idx = map.point2d_to_index(*pos);
-        if map.tiles[idx] == TileType::Exit {
-            new_state = TurnState::NextLevel;
+        match map.tiles[idx] {
+            TileType::Exit => new_state = TurnState::NextLevel,
+            TileType::UpStairs => new_state = TurnState::PreviousLevel,
+            _ => {}
         }
     });
 
@@ -49,9 +104,11 @@ mod tests {
             .add_system(super::end_turn_system())
             .build();
 
-        let map = Map::new();
+        let map = Map::default();
         let player_pos = Point::new(10, 10);
         resources.insert(map);
+        resources.insert(GameConfig::default());
+        resources.insert(TurnCount::default());
         resources.insert(TurnState::AwaitingInput);
 
         ecs.push((
@@ -79,9 +136,11 @@ mod tests {
             .add_system(super::end_turn_system())
             .build();
 
-        let map = Map::new();
+        let map = Map::default();
         let player_pos = Point::new(10, 10);
         resources.insert(map);
+        resources.insert(GameConfig::default());
+        resources.insert(TurnCount::default());
         resources.insert(TurnState::PlayerTurn);
 
         ecs.push((
@@ -109,9 +168,11 @@ mod tests {
             .add_system(super::end_turn_system())
             .build();
 
-        let map = Map::new();
+        let map = Map::default();
         let player_pos = Point::new(10, 10);
         resources.insert(map);
+        resources.insert(GameConfig::default());
+        resources.insert(TurnCount::default());
         resources.insert(TurnState::MonsterTurn);
 
         ecs.push((
@@ -139,9 +200,11 @@ mod tests {
             .add_system(super::end_turn_system())
             .build();
 
-        let map = Map::new();
+        let map = Map::default();
         let player_pos = Point::new(10, 10);
         resources.insert(map);
+        resources.insert(GameConfig::default());
+        resources.insert(TurnCount::default());
         resources.insert(TurnState::PlayerTurn);
 
         ecs.push((
@@ -166,9 +229,11 @@ mod tests {
             .add_system(super::end_turn_system())
             .build();
 
-        let map = Map::new();
+        let map = Map::default();
         let player_pos = Point::new(10, 10);
         resources.insert(map);
+        resources.insert(GameConfig::default());
+        resources.insert(TurnCount::default());
         resources.insert(TurnState::PlayerTurn);
 
         ecs.push((
@@ -194,11 +259,13 @@ mod tests {
             .add_system(super::end_turn_system())
             .build();
 
-        let mut map = Map::new();
+        let mut map = Map::default();
         let player_pos = Point::new(10, 10);
         let idx = map.point2d_to_index(player_pos);
         map.tiles[idx] = TileType::Exit;
         resources.insert(map);
+        resources.insert(GameConfig::default());
+        resources.insert(TurnCount::default());
         resources.insert(TurnState::PlayerTurn);
 
         ecs.push((
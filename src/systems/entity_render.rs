@@ -5,7 +5,13 @@ use crate::prelude::*;
 #[read_component(Render)]
 #[read_component(FieldOfView)]
 #[read_component(Player)]
-pub fn entity_render(ecs: &SubWorld, #[resource] camera: &Camera) {
+#[read_component(Trap)]
+#[read_component(Hidden)]
+pub fn entity_render(
+    ecs: &SubWorld,
+    #[resource] camera: &Camera,
+    #[resource] light_map: &LightMap,
+) {
     let mut player = <(&FieldOfView, &Point, &Render)>::query().filter(component::<Player>());
     let (player_fov, player_pos, player_render) = player.iter(ecs).next().unwrap();
 
@@ -13,22 +19,48 @@ pub fn entity_render(ecs: &SubWorld, #[resource] camera: &Camera) {
     draw_batch.target(1);
     let offset = Point::new(camera.left_x, camera.top_y);
 
-    // Only Items first
+    // Revealed traps first, as anything can stand on them
+    let mut traps =
+        <(&Point, &Render)>::query().filter(component::<Trap>() & !component::<Hidden>());
+    traps
+        .iter(ecs)
+        .filter(|(pos, _)| player_fov.visible_tiles.contains(pos) && *pos != player_pos)
+        .for_each(|(pos, render)| {
+            draw_batch.set(
+                *pos - offset,
+                lit(render.color, light_map, *pos),
+                render.glyph,
+            );
+        });
+
+    // Then Items
     let mut items = <(&Point, &Render)>::query().filter(component::<Item>());
     items
         .iter(ecs)
         .filter(|(pos, _)| player_fov.visible_tiles.contains(pos) && *pos != player_pos)
         .for_each(|(pos, render)| {
-            draw_batch.set(*pos - offset, render.color, render.glyph);
+            draw_batch.set(
+                *pos - offset,
+                lit(render.color, light_map, *pos),
+                render.glyph,
+            );
         });
 
-    // Then monsters/enemies
+    // Then monsters/enemies, which hide in the dark
     let mut enemies = <(&Point, &Render)>::query().filter(component::<Enemy>());
     enemies
         .iter(ecs)
-        .filter(|(pos, _)| player_fov.visible_tiles.contains(pos) && *pos != player_pos)
+        .filter(|(pos, _)| {
+            player_fov.visible_tiles.contains(pos)
+                && *pos != player_pos
+                && light_map.reveals(**pos, *player_pos)
+        })
         .for_each(|(pos, render)| {
-            draw_batch.set(*pos - offset, render.color, render.glyph);
+            draw_batch.set(
+                *pos - offset,
+                lit(render.color, light_map, *pos),
+                render.glyph,
+            );
         });
 
     // Then draw the player, to avoid being "under" items in the screen.
@@ -40,3 +72,17 @@ pub fn entity_render(ecs: &SubWorld, #[resource] camera: &Camera) {
 
     draw_batch.submit(5000).expect("Batch error");
 }
+
+/// Shades the foreground of `color` with the light of the tile at `pos`.
+fn lit(color: ColorPair, light_map: &LightMap, pos: Point) -> ColorPair {
+    let tint = light_map.tint(pos);
+    ColorPair::new(
+        RGBA::from_f32(
+            color.fg.r * tint.r,
+            color.fg.g * tint.g,
+            color.fg.b * tint.b,
+            color.fg.a,
+        ),
+        color.bg,
+    )
+}
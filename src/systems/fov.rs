@@ -27,7 +27,7 @@ mod tests {
         let mut resources = Resources::default();
         let mut sched = Schedule::builder().add_system(super::fov_system()).build();
 
-        let map = Map::new();
+        let map = Map::default();
         resources.insert(map);
 
         let e = ecs.push((
@@ -42,8 +42,8 @@ mod tests {
         sched.execute(&mut ecs, &mut resources);
         let entry = ecs.entry(e).unwrap();
         let fov = entry.get_component::<FieldOfView>().unwrap();
-        assert_eq!(fov.is_dirty, false);
-        assert_eq!(fov.visible_tiles.is_empty(), true);
+        assert!(!fov.is_dirty);
+        assert!(fov.visible_tiles.is_empty());
     }
 
     #[test]
@@ -52,7 +52,7 @@ mod tests {
         let mut resources = Resources::default();
         let mut sched = Schedule::builder().add_system(super::fov_system()).build();
 
-        let map = Map::new();
+        let map = Map::default();
         let center = Point::new(10, 10);
         let mut expected_fov = HashSet::new();
         for dx in -1..=1 {
@@ -76,7 +76,7 @@ mod tests {
         sched.execute(&mut ecs, &mut resources);
         let entry = ecs.entry(e).unwrap();
         let fov = entry.get_component::<FieldOfView>().unwrap();
-        assert_eq!(fov.is_dirty, false);
+        assert!(!fov.is_dirty);
         assert_eq!(fov.visible_tiles, expected_fov);
     }
 }
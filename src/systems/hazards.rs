@@ -0,0 +1,226 @@
+use super::movement::{is_player, player_sees};
+use crate::prelude::*;
+use std::collections::HashSet;
+
+/// Makes a step onto slow terrain at `pos` take longer. Monsters pay for it with their
+/// energy, while the player gives the monsters the time to act some more.
+pub(super) fn take_extra_time(
+    ecs: &SubWorld,
+    commands: &mut CommandBuffer,
+    map: &Map,
+    mover: Entity,
+    pos: Point,
+) {
+    let extra_cost = map.move_cost(map.point2d_to_index(pos)) - ACTION_COST;
+    if extra_cost <= 0 {
+        return;
+    }
+    if is_player(ecs, mover) {
+        <(Entity, Option<&Speed>, Option<&Energy>)>::query()
+            .filter(component::<ChasingPlayer>() | component::<MovingRandomly>())
+            .for_each(ecs, |(entity, speed, energy)| {
+                let speed = speed.map_or(NORMAL_SPEED, |s| s.0);
+                let energy = energy.map_or(0, |e| e.0);
+                commands.add_component(*entity, Energy(energy + speed * extra_cost / ACTION_COST));
+            });
+    } else if let Some(energy) = ecs
+        .entry_ref(mover)
+        .ok()
+        .and_then(|entry| entry.get_component::<Energy>().ok().copied())
+    {
+        commands.add_component(mover, Energy(energy.0 - extra_cost));
+    }
+}
+
+/// Tells the player why they can't step into the deep water at `pos`.
+pub(super) fn refuse_deep_water(
+    ecs: &SubWorld,
+    map: &Map,
+    log: &mut GameLog,
+    mover: Entity,
+    pos: Point,
+) {
+    if map
+        .try_idx(pos)
+        .is_some_and(|idx| map.tiles[idx] == TileType::DeepWater)
+        && is_player(ecs, mover)
+    {
+        log.add("The water is too deep to wade through.", WHITE);
+    }
+}
+
+/// Whether `entity` is a monster that a hazard just killed, and was removed.
+fn killed_monster(ecs: &SubWorld, entity: Entity) -> bool {
+    ecs.entry_ref(entity).is_ok_and(|entry| {
+        entry.get_component::<Player>().is_err()
+            && entry
+                .get_component::<Health>()
+                .is_ok_and(|health| health.current < 1)
+    })
+}
+
+/// What the hazards of the level need to act on whoever walks into them.
+pub(super) struct Hazards<'a> {
+    pub(super) commands: &'a mut CommandBuffer,
+    pub(super) map: &'a Map,
+    pub(super) rng: &'a mut RandomNumberGenerator,
+    pub(super) log: &'a mut GameLog,
+    pub(super) stats: &'a mut RunStats,
+    pub(super) occupied_spaces: &'a HashSet<Point>,
+}
+
+impl Hazards<'_> {
+    /// Sets off the trap waiting for `mover` at `destination`, then burns them if they end
+    /// up in lava. Returns where they end up, or `None` if they were a monster and died.
+    pub(super) fn enter(
+        &mut self,
+        ecs: &mut SubWorld,
+        mover: Entity,
+        destination: Point,
+    ) -> Option<Point> {
+        let trap = <(Entity, &Point)>::query()
+            .filter(component::<Trap>())
+            .iter(ecs)
+            .find(|(_, pos)| **pos == destination)
+            .map(|(trap, _)| *trap);
+        let destination = trap
+            .and_then(|trap| self.spring(ecs, trap, mover))
+            .unwrap_or(destination);
+        if self.map.tiles[self.map.point2d_to_index(destination)] == TileType::Lava
+            && !killed_monster(ecs, mover)
+        {
+            self.burn(ecs, mover, destination);
+        }
+        (!killed_monster(ecs, mover)).then_some(destination)
+    }
+
+    /// Sets `trap` off on `victim`, revealing it. Returns where the victim ends up instead
+    /// of the trap, if the trap sends them elsewhere.
+    fn spring(&mut self, ecs: &mut SubWorld, trap: Entity, victim: Entity) -> Option<Point> {
+        let trap_entry = ecs.entry_ref(trap).ok()?;
+        let trap_name = trap_entry.get_component::<Name>().ok()?.0.clone();
+        let damage = trap_entry.get_component::<Damage>().map(|d| d.0).ok();
+        let teleports = trap_entry.get_component::<Teleports>().is_ok();
+        let sounds_alarm = trap_entry.get_component::<SoundsAlarm>().is_ok();
+        let poisons = trap_entry.get_component::<Poisons>().ok().copied();
+        self.commands.remove_component::<Hidden>(trap);
+
+        let victim_entry = ecs.entry_ref(victim).ok()?;
+        let is_player = victim_entry.get_component::<Player>().is_ok();
+        let victim_name = victim_entry
+            .get_component::<Name>()
+            .map(|name| name.0.clone())
+            .unwrap_or_else(|_| "something".to_string());
+        // Monsters setting off traps are only reported when the player sees it happen.
+        let trap_pos = *trap_entry.get_component::<Point>().ok()?;
+        let seen = is_player || player_sees(ecs, trap_pos);
+        if is_player {
+            self.log
+                .add(format!("You trigger a {}!", trap_name), ORANGE);
+        } else if seen {
+            self.log.add(
+                format!("The {} triggers a {}!", victim_name, trap_name),
+                ORANGE,
+            );
+        }
+
+        if let Some(damage) = damage {
+            if is_player {
+                self.log.add(format!("It hits you for {}.", damage), RED);
+            }
+            self.hurt(ecs, victim, damage, SlainBy::Trap(trap_name.clone()), seen);
+        }
+        if let Some(poisons) = poisons.filter(|_| !killed_monster(ecs, victim)) {
+            self.commands.add_component(
+                victim,
+                Poisoned {
+                    turns: poisons.turns,
+                    source: trap_name.clone(),
+                },
+            );
+            if is_player {
+                self.log.add("You choke on the fumes and feel sick.", GREEN);
+            }
+        }
+        if sounds_alarm {
+            // Monsters killed by traps this turn are already on their way out of the world.
+            <(Entity, &Health)>::query()
+                .filter(component::<Enemy>())
+                .iter(ecs)
+                .filter(|(_, health)| health.current > 0)
+                .for_each(|(monster, _)| self.commands.add_component(*monster, Alerted));
+            self.log.add("An alarm blares through the level!", ORANGE);
+        }
+        if teleports && !killed_monster(ecs, victim) {
+            let destination = self.random_free_tile();
+            if is_player && destination.is_some() {
+                self.log.add("The world spins around you.", MAGENTA);
+            }
+            return destination;
+        }
+        None
+    }
+
+    /// Burns `victim` for stepping onto the lava at `pos`.
+    fn burn(&mut self, ecs: &mut SubWorld, victim: Entity, pos: Point) {
+        let seen = player_sees(ecs, pos);
+        if is_player(ecs, victim) {
+            self.log
+                .add(format!("The lava burns you for {}.", LAVA_DAMAGE), RED);
+        }
+        self.hurt(ecs, victim, LAVA_DAMAGE, SlainBy::Lava, seen);
+    }
+
+    /// Deals `damage` caused by `cause` to `victim`. Monsters that die of it are removed.
+    fn hurt(
+        &mut self,
+        ecs: &mut SubWorld,
+        victim: Entity,
+        damage: i32,
+        cause: SlainBy,
+        seen: bool,
+    ) {
+        let Ok(mut entry) = ecs.entry_mut(victim) else {
+            return;
+        };
+        let is_player = entry.get_component::<Player>().is_ok();
+        let victim_name = entry
+            .get_component::<Name>()
+            .map(|name| name.0.clone())
+            .unwrap_or_else(|_| "something".to_string());
+        let Ok(health) = entry.get_component_mut::<Health>() else {
+            return;
+        };
+        health.current -= damage;
+        if is_player {
+            self.stats.damage_taken += damage.max(0) as u32;
+        }
+        if health.current < 1 {
+            if is_player {
+                self.log.add("You die...", RED);
+                self.commands.add_component(victim, cause);
+            } else {
+                if seen {
+                    self.log.add(format!("The {} dies.", victim_name), YELLOW);
+                }
+                self.commands.remove(victim);
+            }
+        }
+    }
+
+    fn random_free_tile(&mut self) -> Option<Point> {
+        (0..100)
+            .map(|_| {
+                Point::new(
+                    self.rng.range(0, self.map.width),
+                    self.rng.range(0, self.map.height),
+                )
+            })
+            .find(|pt| {
+                self.map
+                    .try_idx(*pt)
+                    .is_some_and(|idx| self.map.tiles[idx] == TileType::Floor)
+                    && !self.occupied_spaces.contains(pt)
+            })
+    }
+}
@@ -7,13 +7,25 @@ use crate::prelude::*;
 #[read_component(Carried)]
 #[read_component(Name)]
 #[read_component(Point)]
-pub fn hud(ecs: &SubWorld) {
+pub fn hud(
+    ecs: &SubWorld,
+    #[resource] key_bindings: &KeyBindings,
+    #[resource] config: &GameConfig,
+    #[resource] turn_count: &TurnCount,
+    #[resource] log: &GameLog,
+) {
     let mut player_query = <(&Health, &Point)>::query().filter(component::<Player>());
     let (player_health, player_pos) = player_query.iter(ecs).next().unwrap();
 
     let mut draw_batch = DrawBatch::new();
     draw_batch.target(2);
-    draw_batch.print_centered(1, "Explore the Dungeon. Cursor keys to move.");
+    draw_batch.print_centered(
+        1,
+        format!(
+            "{} Escape for the menu.",
+            config.victory.describe(turn_count.0)
+        ),
+    );
     draw_batch.bar_horizontal(
         Point::zero(),
         SCREEN_WIDTH * 2,
@@ -37,7 +49,11 @@ pub fn hud(ecs: &SubWorld) {
         .unwrap();
     draw_batch.print_color_right(
         Point::new(SCREEN_WIDTH * 2, 1),
-        format!("Dungeon Level: {}", map_level + 1),
+        format!(
+            "Dungeon Level: {} of {}",
+            map_level + 1,
+            config.dungeon_depth
+        ),
         ColorPair::new(YELLOW, BLACK),
     );
 
@@ -48,11 +64,14 @@ pub fn hud(ecs: &SubWorld) {
         .map(|(name, _)| name)
         .next()
     {
+        let grab_key = key_bindings
+            .key_name(Action::PickUp)
+            .unwrap_or_else(|| "nothing".to_string());
         draw_batch.print_color_centered(
             2,
             format!(
-                " You see a {} near your feet (press 'g' to grab).",
-                item_under.0
+                " You see a {} near your feet (press {} to grab).",
+                item_under.0, grab_key
             ),
             ColorPair::new(GREEN, BLACK),
         );
@@ -76,5 +95,39 @@ pub fn hud(ecs: &SubWorld) {
         );
     }
 
+    // The message log panel, at the bottom of the screen.
+    let panel_top = SCREEN_HEIGHT * 2 - LOG_PANEL_LINES as i32 - 2;
+    draw_batch.draw_box(
+        Rect::with_size(
+            0,
+            panel_top,
+            SCREEN_WIDTH * 2 - 1,
+            LOG_PANEL_LINES as i32 + 1,
+        ),
+        ColorPair::new(GRAY, BLACK),
+    );
+    let scroll_keys = [Action::ScrollLogUp, Action::ScrollLogDown]
+        .map(|action| {
+            key_bindings
+                .key_name(action)
+                .unwrap_or_else(|| "nothing".to_string())
+        })
+        .join("/");
+    draw_batch.print_color(
+        Point::new(2, panel_top),
+        format!(" Messages ({} to scroll) ", scroll_keys),
+        ColorPair::new(GRAY, BLACK),
+    );
+    log.panel_entries()
+        .iter()
+        .enumerate()
+        .for_each(|(n, entry)| {
+            draw_batch.print_color(
+                Point::new(2, panel_top + 1 + n as i32),
+                &entry.text,
+                ColorPair::new(entry.color, BLACK),
+            );
+        });
+
     draw_batch.submit(10000).expect("Batch error");
 }
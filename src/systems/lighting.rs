@@ -0,0 +1,38 @@
+use crate::prelude::*;
+
+/// Computes how the level is lit. When `every_turn` is false, the light is only computed
+/// for a new level.
+#[system]
+#[allow(clippy::borrowed_box)]
+#[read_component(Point)]
+#[read_component(LightSource)]
+#[read_component(Carried)]
+pub fn lighting(
+    ecs: &SubWorld,
+    #[resource] map: &Map,
+    #[resource] theme: &Box<dyn MapTheme>,
+    #[resource] light_map: &mut LightMap,
+    #[state] every_turn: &bool,
+) {
+    if !*every_turn && !light_map.is_dirty && light_map.fits(map) {
+        return;
+    }
+
+    let mut sources: Vec<(Point, LightSource)> = <(&Point, &LightSource)>::query()
+        .iter(ecs)
+        .map(|(pos, source)| (*pos, *source))
+        .collect();
+    // Carried lights go wherever their carrier goes.
+    <(&Carried, &LightSource)>::query()
+        .iter(ecs)
+        .for_each(|(carried, source)| {
+            if let Some(pos) = ecs
+                .entry_ref(carried.0)
+                .ok()
+                .and_then(|entry| entry.get_component::<Point>().ok().copied())
+            {
+                sources.push((pos, *source));
+            }
+        });
+    light_map.compute(map, theme.ambient_light(), &sources);
+}
@@ -1,6 +1,10 @@
 use crate::prelude::*;
 
+/// The color of the tiles out of view, darker than any tile in view.
+const REMEMBERED_TINT: (u8, u8, u8) = (60, 60, 70);
+
 #[system]
+#[allow(clippy::borrowed_box)]
 #[read_component(FieldOfView)]
 #[read_component(Player)]
 pub fn map_render(
@@ -8,6 +12,7 @@ pub fn map_render(
     #[resource] map: &Map,
     #[resource] camera: &Camera,
     #[resource] theme: &Box<dyn MapTheme>,
+    #[resource] light_map: &LightMap,
 ) {
     let mut fov = <&FieldOfView>::query().filter(component::<Player>());
     let player_fov = fov.iter(ecs).next().unwrap();
@@ -19,14 +24,14 @@ pub fn map_render(
             let pt = Point::new(x, y);
             let offset = Point::new(camera.left_x, camera.top_y);
 
-            let idx = map_idx(x, y);
-            if map.in_bounds(pt)
-                && (player_fov.visible_tiles.contains(&pt) || map.revealed_tiles[idx])
-            {
+            let Some(idx) = map.try_idx(pt) else {
+                continue;
+            };
+            if player_fov.visible_tiles.contains(&pt) || map.revealed_tiles[idx] {
                 let tint = if player_fov.visible_tiles.contains(&pt) {
-                    WHITE
+                    light_map.tint(pt)
                 } else {
-                    DARK_GRAY
+                    REMEMBERED_TINT.into()
                 };
                 let glyph = theme.tile_to_render(map.tiles[idx]);
                 draw_batch.set(pt - offset, ColorPair::new(tint, BLACK), glyph);
@@ -1,32 +1,50 @@
 mod chasing;
 mod combat;
+mod digging;
 mod end_turn;
 mod entity_render;
 mod fov;
+mod hazards;
 mod hud;
+mod lighting;
 mod map_render;
 mod movement;
 mod player_input;
+mod poison;
 mod random_move;
+mod secrets;
 mod tooltips;
 mod use_items;
 
 use crate::prelude::*;
 
-pub fn build_input_scheduler() -> Schedule {
-    Schedule::builder()
-        .add_system(player_input::player_input_system())
-        .add_system(fov::fov_system())
-        .flush()
+use legion::systems::Builder;
+
+/// Adds the systems that draw the map, the entities and the UI to the draw batches.
+fn add_render_systems(builder: &mut Builder) -> &mut Builder {
+    builder
         .add_system(map_render::map_render_system())
         .add_system(entity_render::entity_render_system())
         .add_system(hud::hud_system())
         .add_system(tooltips::tooltips_system())
-        .build()
 }
 
-pub fn build_player_scheduler() -> Schedule {
-    Schedule::builder()
+pub fn build_input_scheduler(render: bool) -> Schedule {
+    let mut builder = Schedule::builder();
+    builder
+        .add_system(player_input::player_input_system())
+        .add_system(fov::fov_system())
+        .flush()
+        .add_system(lighting::lighting_system(false));
+    if render {
+        add_render_systems(&mut builder);
+    }
+    builder.build()
+}
+
+pub fn build_player_scheduler(render: bool) -> Schedule {
+    let mut builder = Schedule::builder();
+    builder
         .add_system(use_items::use_items_system())
         .add_system(combat::combat_system())
         .flush()
@@ -34,16 +52,18 @@ pub fn build_player_scheduler() -> Schedule {
         .flush()
         .add_system(fov::fov_system())
         .flush()
-        .add_system(map_render::map_render_system())
-        .add_system(entity_render::entity_render_system())
-        .add_system(hud::hud_system())
-        .add_system(tooltips::tooltips_system())
-        .add_system(end_turn::end_turn_system())
-        .build()
+        .add_system(poison::poison_system())
+        .add_system(lighting::lighting_system(true))
+        .flush();
+    if render {
+        add_render_systems(&mut builder);
+    }
+    builder.add_system(end_turn::end_turn_system()).build()
 }
 
-pub fn build_monster_scheduler() -> Schedule {
-    Schedule::builder()
+pub fn build_monster_scheduler(render: bool) -> Schedule {
+    let mut builder = Schedule::builder();
+    builder
         .add_system(random_move::random_move_system())
         .add_system(chasing::chasing_system())
         .flush()
@@ -54,10 +74,9 @@ pub fn build_monster_scheduler() -> Schedule {
         .flush()
         .add_system(fov::fov_system())
         .flush()
-        .add_system(map_render::map_render_system())
-        .add_system(entity_render::entity_render_system())
-        .add_system(hud::hud_system())
-        .add_system(tooltips::tooltips_system())
-        .add_system(end_turn::end_turn_system())
-        .build()
+        .add_system(lighting::lighting_system(true));
+    if render {
+        add_render_systems(&mut builder);
+    }
+    builder.add_system(end_turn::end_turn_system()).build()
 }
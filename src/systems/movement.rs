@@ -1,3 +1,6 @@
+use super::digging::dig;
+use super::hazards::{refuse_deep_water, take_extra_time, Hazards};
+use super::secrets::spot_secrets;
 use crate::prelude::*;
 use std::collections::HashSet;
 
@@ -5,13 +8,32 @@ use std::collections::HashSet;
 #[read_component(Player)]
 #[read_component(Enemy)]
 #[read_component(FieldOfView)]
+#[read_component(ChasingPlayer)]
 #[read_component(WantsToMove)]
+#[read_component(Name)]
+#[read_component(Trap)]
+#[read_component(Hidden)]
+#[read_component(Damage)]
+#[read_component(Teleports)]
+#[read_component(SoundsAlarm)]
+#[read_component(Poisons)]
+#[read_component(MovingRandomly)]
+#[read_component(Speed)]
+#[read_component(Energy)]
+#[read_component(Digger)]
+#[read_component(Digging)]
+#[read_component(Carried)]
+#[write_component(Health)]
 #[write_component(Point)]
+#[allow(clippy::too_many_arguments)]
 pub fn movement(
     ecs: &mut SubWorld,
     commands: &mut CommandBuffer,
     #[resource] map: &mut Map,
     #[resource] camera: &mut Camera,
+    #[resource] stats: &mut RunStats,
+    #[resource] log: &mut GameLog,
+    #[resource] rng: &mut RandomNumberGenerator,
 ) {
     let mut occupied_spaces = HashSet::new();
     <&Point>::query()
@@ -27,28 +49,101 @@ pub fn movement(
             occupied_spaces.insert(*p);
         });
 
-    let mut movers = <(Entity, &WantsToMove)>::query();
-    movers.iter(ecs).for_each(|(e, want_move)| {
-        if map.can_enter_tile(want_move.destination)
+    // Traps can hurt the movers, so the moves are collected before the world is changed.
+    let moves: Vec<(Entity, WantsToMove)> = <(Entity, &WantsToMove)>::query()
+        .iter(ecs)
+        .map(|(e, want_move)| (*e, *want_move))
+        .collect();
+    moves.iter().for_each(|(e, want_move)| {
+        if !map.can_step(want_move.from, want_move.destination - want_move.from) {
+            // Corners and doorways can't be cut diagonally, so the move just takes the turn.
+        } else if map.can_enter_tile(want_move.destination)
             && !occupied_spaces.contains(&want_move.destination)
         {
+            let mut hazards = Hazards {
+                commands: &mut *commands,
+                map,
+                rng: &mut *rng,
+                log: &mut *log,
+                stats: &mut *stats,
+                occupied_spaces: &occupied_spaces,
+            };
+            let destination = hazards.enter(ecs, want_move.entity, want_move.destination);
             occupied_spaces.remove(&want_move.from);
-            occupied_spaces.insert(want_move.destination);
-            commands.add_component(want_move.entity, want_move.destination);
+            let Some(destination) = destination else {
+                commands.remove(*e);
+                return;
+            };
+            occupied_spaces.insert(destination);
+            commands.add_component(want_move.entity, destination);
+            take_extra_time(ecs, commands, map, want_move.entity, destination);
 
             if let Ok(entry) = ecs.entry_ref(want_move.entity) {
                 if let Ok(fov) = entry.get_component::<FieldOfView>() {
                     commands.add_component(want_move.entity, fov.clone_dirty());
 
                     if entry.get_component::<Player>().is_ok() {
-                        camera.on_player_move(want_move.destination);
-                        fov.visible_tiles
-                            .iter()
-                            .for_each(|pos| map.revealed_tiles[map_idx(pos.x, pos.y)] = true);
+                        camera.on_player_move(destination);
+                        fov.visible_tiles.iter().for_each(|pos| {
+                            let idx = map.map_idx(pos.x, pos.y);
+                            if !map.revealed_tiles[idx] {
+                                map.revealed_tiles[idx] = true;
+                                stats.tiles_explored += 1;
+                            }
+                        });
+                        spot_secrets(ecs, commands, map, rng, log, destination, 3);
                     }
                 }
             }
+        } else if map.is_closed_door(want_move.destination) {
+            // The player and the monsters hunting them open the doors they bump into.
+            let entry = ecs.entry_ref(want_move.entity);
+            let is_player = entry
+                .as_ref()
+                .is_ok_and(|entry| entry.get_component::<Player>().is_ok());
+            let is_hunter = entry
+                .as_ref()
+                .is_ok_and(|entry| entry.get_component::<ChasingPlayer>().is_ok());
+            if is_player || is_hunter {
+                let idx = map.point2d_to_index(want_move.destination);
+                map.tiles[idx] = TileType::DoorOpen;
+                if is_player {
+                    log.add("You open the door.", WHITE);
+                }
+                // What can be seen through the door changes for everyone.
+                refresh_views(ecs, commands);
+            }
+        } else if map.can_dig(want_move.destination) {
+            dig(
+                ecs,
+                commands,
+                map,
+                log,
+                want_move.entity,
+                want_move.destination,
+            );
+        } else {
+            refuse_deep_water(ecs, map, log, want_move.entity, want_move.destination);
         }
         commands.remove(*e)
     });
 }
+
+/// Makes everyone look around again, e.g. once a door or a wall is gone.
+pub(crate) fn refresh_views(ecs: &SubWorld, commands: &mut CommandBuffer) {
+    <(Entity, &FieldOfView)>::query()
+        .iter(ecs)
+        .for_each(|(entity, fov)| commands.add_component(*entity, fov.clone_dirty()));
+}
+
+pub(super) fn is_player(ecs: &SubWorld, entity: Entity) -> bool {
+    ecs.entry_ref(entity)
+        .is_ok_and(|entry| entry.get_component::<Player>().is_ok())
+}
+
+pub(super) fn player_sees(ecs: &SubWorld, pos: Point) -> bool {
+    <&FieldOfView>::query()
+        .filter(component::<Player>())
+        .iter(ecs)
+        .any(|fov| fov.visible_tiles.contains(&pos))
+}
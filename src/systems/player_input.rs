@@ -1,3 +1,5 @@
+use super::movement::refresh_views;
+use super::secrets::spot_secrets;
 use crate::prelude::*;
 
 #[system]
@@ -8,21 +10,44 @@ use crate::prelude::*;
 #[read_component(Item)]
 #[read_component(Carried)]
 #[read_component(Weapon)]
+#[read_component(Name)]
+#[read_component(FieldOfView)]
+#[read_component(Trap)]
+#[read_component(Hidden)]
+#[read_component(Digger)]
+#[allow(clippy::too_many_arguments)]
 pub fn player_input(
     ecs: &mut SubWorld,
     commands: &mut CommandBuffer,
     #[resource] key: &Option<VirtualKeyCode>,
+    #[resource] key_bindings: &KeyBindings,
     #[resource] turn_state: &mut TurnState,
+    #[resource] log: &mut GameLog,
+    #[resource] map: &mut Map,
+    #[resource] rng: &mut RandomNumberGenerator,
 ) {
     let mut players = <(Entity, &Point)>::query().filter(component::<Player>());
 
-    if let Some(key) = key {
-        let delta = match key {
-            VirtualKeyCode::Left => Point::new(-1, 0),
-            VirtualKeyCode::Right => Point::new(1, 0),
-            VirtualKeyCode::Up => Point::new(0, -1),
-            VirtualKeyCode::Down => Point::new(0, 1),
-            VirtualKeyCode::G => {
+    // Like in the original controls, a key that isn't bound to anything passes the turn.
+    if let Some(action) = key.map(|key| key_bindings.action(key).unwrap_or(Action::Wait)) {
+        let delta = match action {
+            Action::MoveWest => Point::new(-1, 0),
+            Action::MoveEast => Point::new(1, 0),
+            Action::MoveNorth => Point::new(0, -1),
+            Action::MoveSouth => Point::new(0, 1),
+            Action::MoveNorthEast
+            | Action::MoveNorthWest
+            | Action::MoveSouthEast
+            | Action::MoveSouthWest
+                if !map.diagonal_movement =>
+            {
+                return;
+            }
+            Action::MoveNorthEast => Point::new(1, -1),
+            Action::MoveNorthWest => Point::new(-1, -1),
+            Action::MoveSouthEast => Point::new(1, 1),
+            Action::MoveSouthWest => Point::new(-1, 1),
+            Action::PickUp => {
                 let (player, player_pos) = players
                     .iter(ecs)
                     .map(|(entity, pos)| (*entity, *pos))
@@ -38,12 +63,11 @@ pub fn player_input(
                         commands.remove_component::<Point>(*entity);
                         commands.add_component(*entity, Carried(player));
 
-                        if ecs
-                            .entry_ref(*entity)
-                            .unwrap()
-                            .get_component::<Weapon>()
-                            .is_ok()
-                        {
+                        let item = ecs.entry_ref(*entity).unwrap();
+                        if let Ok(name) = item.get_component::<Name>() {
+                            log.add(format!("You pick up the {}.", name.0), CYAN);
+                        }
+                        if item.get_component::<Weapon>().is_ok() {
                             new_weapon = Some(entity);
                         }
                     });
@@ -55,26 +79,52 @@ pub fn player_input(
                         .filter(component::<Carried>())
                         .filter(component::<Weapon>())
                         .iter(ecs)
-                        .filter(|e| *e != new_weapon)
-                        .next()
+                        .find(|e| *e != new_weapon)
                     {
                         commands.remove_component::<Carried>(*old_weapon);
-                        commands.add_component(*old_weapon, player_pos.clone());
+                        commands.add_component(*old_weapon, player_pos);
+                        if let Ok(name) =
+                            ecs.entry_ref(*old_weapon).unwrap().get_component::<Name>()
+                        {
+                            log.add(format!("You drop the {}.", name.0), CYAN);
+                        }
                     }
                 }
 
                 Point::new(0, 0)
             }
-            VirtualKeyCode::Key1 => use_item(0, ecs, commands),
-            VirtualKeyCode::Key2 => use_item(1, ecs, commands),
-            VirtualKeyCode::Key3 => use_item(2, ecs, commands),
-            VirtualKeyCode::Key4 => use_item(3, ecs, commands),
-            VirtualKeyCode::Key5 => use_item(4, ecs, commands),
-            VirtualKeyCode::Key6 => use_item(5, ecs, commands),
-            VirtualKeyCode::Key7 => use_item(6, ecs, commands),
-            VirtualKeyCode::Key8 => use_item(7, ecs, commands),
-            VirtualKeyCode::Key9 => use_item(8, ecs, commands),
-            _ => Point::new(0, 0),
+            Action::CloseDoor => {
+                if !close_door(ecs, commands, map, log) {
+                    return;
+                }
+                Point::zero()
+            }
+            Action::Search => {
+                let player_pos = players.iter(ecs).map(|(_, pos)| *pos).next().unwrap();
+                if spot_secrets(ecs, commands, map, rng, log, player_pos, 1) == 0 {
+                    log.add("You search around, but find nothing.", GRAY);
+                }
+                Point::zero()
+            }
+            Action::UseItem(n) => {
+                if !use_item(n, ecs, commands, log) {
+                    return;
+                }
+                Point::zero()
+            }
+            Action::SaveGame => {
+                *turn_state = TurnState::SaveGame;
+                return;
+            }
+            Action::ScrollLogUp => {
+                log.scroll_by(1);
+                return;
+            }
+            Action::ScrollLogDown => {
+                log.scroll_by(-1);
+                return;
+            }
+            Action::Wait => Point::new(0, 0),
         };
 
         let (player_entity, player_pos) = players
@@ -116,7 +166,48 @@ pub fn player_input(
     }
 }
 
-fn use_item(n: usize, ecs: &mut SubWorld, commands: &mut CommandBuffer) -> Point {
+/// Closes an open door next to the player, unless something stands in the doorway.
+/// Returns false, without spending the turn, when there is no door to close.
+fn close_door(
+    ecs: &SubWorld,
+    commands: &mut CommandBuffer,
+    map: &mut Map,
+    log: &mut GameLog,
+) -> bool {
+    let player_pos = *<&Point>::query()
+        .filter(component::<Player>())
+        .iter(ecs)
+        .next()
+        .unwrap();
+    let mut occupied = <&Point>::query().filter(component::<Health>());
+    let door = [
+        Point::new(0, -1),
+        Point::new(0, 1),
+        Point::new(-1, 0),
+        Point::new(1, 0),
+    ]
+    .iter()
+    .map(|delta| player_pos + *delta)
+    .find(|pt| {
+        map.try_idx(*pt)
+            .is_some_and(|idx| map.tiles[idx] == TileType::DoorOpen)
+            && !occupied.iter(ecs).any(|pos| pos == pt)
+    });
+
+    let Some(door) = door else {
+        log.add("There is no open door to close.", GRAY);
+        return false;
+    };
+    let idx = map.point2d_to_index(door);
+    map.tiles[idx] = TileType::DoorClosed;
+    log.add("You close the door.", WHITE);
+    refresh_views(ecs, commands);
+    true
+}
+
+/// Uses the n-th carried item. Returns false, without spending the turn, when the item is a
+/// tool: digging is done by walking into the walls.
+fn use_item(n: usize, ecs: &mut SubWorld, commands: &mut CommandBuffer, log: &mut GameLog) -> bool {
     let player_entity = <(Entity, &Player)>::query()
         .iter(ecs)
         .map(|(entity, _)| *entity)
@@ -131,6 +222,19 @@ fn use_item(n: usize, ecs: &mut SubWorld, commands: &mut CommandBuffer) -> Point
         .next();
 
     if let Some(item_entity) = item_entity {
+        if let Ok(item) = ecs.entry_ref(item_entity) {
+            if item.get_component::<Digger>().is_ok() {
+                let name = item
+                    .get_component::<Name>()
+                    .map(|name| name.0.clone())
+                    .unwrap_or_default();
+                log.add(
+                    format!("You dig with the {} by walking into a wall.", name),
+                    GRAY,
+                );
+                return false;
+            }
+        }
         commands.push((
             (),
             ActivateItem {
@@ -139,5 +243,5 @@ fn use_item(n: usize, ecs: &mut SubWorld, commands: &mut CommandBuffer) -> Point
             },
         ));
     }
-    Point::zero()
+    true
 }
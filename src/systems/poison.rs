@@ -0,0 +1,63 @@
+use crate::prelude::*;
+
+/// Poisoned entities lose 1 HP at the end of every turn of the player, until the poison
+/// wears off.
+#[system]
+#[read_component(Player)]
+#[read_component(Name)]
+#[read_component(Poisoned)]
+#[write_component(Health)]
+pub fn poison(
+    ecs: &mut SubWorld,
+    commands: &mut CommandBuffer,
+    #[resource] log: &mut GameLog,
+    #[resource] stats: &mut RunStats,
+) {
+    let poisoned: Vec<(Entity, Poisoned)> = <(Entity, &Poisoned)>::query()
+        .iter(ecs)
+        .map(|(entity, poisoned)| (*entity, poisoned.clone()))
+        .collect();
+
+    poisoned.into_iter().for_each(|(entity, poisoned)| {
+        let mut entry = ecs.entry_mut(entity).unwrap();
+        let is_player = entry.get_component::<Player>().is_ok();
+        let name = entry
+            .get_component::<Name>()
+            .map(|name| name.0.clone())
+            .unwrap_or_default();
+        let Ok(health) = entry.get_component_mut::<Health>() else {
+            return;
+        };
+        if health.current < 1 {
+            return;
+        }
+
+        health.current -= 1;
+        if is_player {
+            stats.damage_taken += 1;
+            log.add("The poison burns you (-1 HP).", GREEN);
+        }
+        if health.current < 1 {
+            if is_player {
+                log.add("You die...", RED);
+                commands.add_component(entity, SlainBy::Poison(poisoned.source));
+            } else {
+                log.add(format!("The {} succumbs to the poison.", name), YELLOW);
+                commands.remove(entity);
+            }
+        } else if poisoned.turns > 1 {
+            commands.add_component(
+                entity,
+                Poisoned {
+                    turns: poisoned.turns - 1,
+                    source: poisoned.source,
+                },
+            );
+        } else {
+            commands.remove_component::<Poisoned>(entity);
+            if is_player {
+                log.add("You feel better.", GREEN);
+            }
+        }
+    });
+}
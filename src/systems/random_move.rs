@@ -5,18 +5,23 @@ use crate::prelude::*;
 #[read_component(MovingRandomly)]
 #[read_component(Health)]
 #[read_component(Player)]
-pub fn random_move(ecs: &mut SubWorld, commands: &mut CommandBuffer) {
-    let mut movers = <(Entity, &Point, &MovingRandomly)>::query();
+#[read_component(Energy)]
+pub fn random_move(
+    ecs: &mut SubWorld,
+    commands: &mut CommandBuffer,
+    #[resource] rng: &mut RandomNumberGenerator,
+    #[resource] map: &Map,
+) {
+    let mut movers = <(Entity, &Point, &MovingRandomly, &Energy)>::query();
     let mut positions = <(Entity, &Point, &Health)>::query();
 
-    movers.iter(ecs).for_each(|(entity, pos, _)| {
-        let mut rng = RandomNumberGenerator::new();
-        let destination = match rng.range(0, 4) {
-            0 => Point::new(-1, 0),
-            1 => Point::new(1, 0),
-            2 => Point::new(0, -1),
-            _ => Point::new(0, 1),
-        } + *pos;
+    movers.iter(ecs).for_each(|(entity, pos, _, energy)| {
+        if energy.0 < ACTION_COST {
+            return;
+        }
+        commands.add_component(*entity, Energy(energy.0 - ACTION_COST));
+        let directions = map.directions();
+        let destination = directions[rng.range(0, directions.len())] + *pos;
 
         let mut attacked = false;
         positions
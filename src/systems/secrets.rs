@@ -0,0 +1,43 @@
+use crate::prelude::*;
+
+/// Gives the player a one in `odds` chance to notice each hidden trap and secret door next
+/// to them. Returns how many they found.
+pub(super) fn spot_secrets(
+    ecs: &SubWorld,
+    commands: &mut CommandBuffer,
+    map: &mut Map,
+    rng: &mut RandomNumberGenerator,
+    log: &mut GameLog,
+    player_pos: Point,
+    odds: i32,
+) -> usize {
+    let mut found = 0;
+    <(Entity, &Point, &Name)>::query()
+        .filter(component::<Trap>() & component::<Hidden>())
+        .iter(ecs)
+        .filter(|(_, pos, _)| {
+            let delta = **pos - player_pos;
+            delta.x.abs() <= 1 && delta.y.abs() <= 1 && **pos != player_pos
+        })
+        .for_each(|(trap, _, name)| {
+            if rng.range(0, odds) == 0 {
+                commands.remove_component::<Hidden>(*trap);
+                log.add(format!("You spot a {}.", name.0), ORANGE);
+                found += 1;
+            }
+        });
+    DIRECTIONS
+        .iter()
+        .filter_map(|delta| map.try_idx(player_pos + *delta))
+        .filter(|idx| map.tiles[*idx] == TileType::DoorSecret)
+        .collect::<Vec<usize>>()
+        .into_iter()
+        .for_each(|idx| {
+            if rng.range(0, odds) == 0 {
+                map.tiles[idx] = TileType::DoorClosed;
+                log.add("You find a secret door!", ORANGE);
+                found += 1;
+            }
+        });
+    found
+}
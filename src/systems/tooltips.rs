@@ -6,10 +6,17 @@ use crate::prelude::*;
 #[read_component(Health)]
 #[read_component(FieldOfView)]
 #[read_component(Player)]
-pub fn tooltips(ecs: &SubWorld, #[resource] mouse_pos: &Point, #[resource] camera: &Camera) {
-    let mut positions = <(Entity, &Point, &Name)>::query();
-    let mut fov = <&FieldOfView>::query().filter(component::<Player>());
-    let player_fov = fov.iter(ecs).next().unwrap();
+#[read_component(Hidden)]
+#[read_component(Enemy)]
+pub fn tooltips(
+    ecs: &SubWorld,
+    #[resource] mouse_pos: &Point,
+    #[resource] camera: &Camera,
+    #[resource] light_map: &LightMap,
+) {
+    let mut positions = <(Entity, &Point, &Name)>::query().filter(!component::<Hidden>());
+    let mut fov = <(&FieldOfView, &Point)>::query().filter(component::<Player>());
+    let (player_fov, player_pos) = fov.iter(ecs).next().unwrap();
 
     let offset = Point::new(camera.left_x, camera.top_y);
     let map_pos = *mouse_pos + offset;
@@ -19,6 +26,13 @@ pub fn tooltips(ecs: &SubWorld, #[resource] mouse_pos: &Point, #[resource] camer
     positions
         .iter(ecs)
         .filter(|(_, pos, _)| **pos == map_pos && player_fov.visible_tiles.contains(pos))
+        // The monsters in the dark can't be seen
+        .filter(|(entity, pos, _)| {
+            light_map.reveals(**pos, *player_pos)
+                || ecs
+                    .entry_ref(**entity)
+                    .is_ok_and(|entry| entry.get_component::<Enemy>().is_err())
+        })
         .for_each(|(entity, _, name)| {
             let screen_pos = *mouse_pos * 4;
             let display =
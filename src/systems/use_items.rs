@@ -4,8 +4,18 @@ use crate::prelude::*;
 #[read_component(ActivateItem)]
 #[read_component(ProvidesHealing)]
 #[read_component(ProvidesDungeonMap)]
+#[read_component(Name)]
+#[read_component(Explodes)]
+#[read_component(Point)]
+#[read_component(FieldOfView)]
 #[write_component(Health)]
-pub fn use_items(ecs: &mut SubWorld, commands: &mut CommandBuffer, #[resource] map: &mut Map) {
+pub fn use_items(
+    ecs: &mut SubWorld,
+    commands: &mut CommandBuffer,
+    #[resource] map: &mut Map,
+    #[resource] log: &mut GameLog,
+    #[resource] stats: &mut RunStats,
+) {
     let mut healing_to_apply = Vec::<(Entity, i32)>::new();
 
     <(Entity, &ActivateItem)>::query()
@@ -13,11 +23,43 @@ pub fn use_items(ecs: &mut SubWorld, commands: &mut CommandBuffer, #[resource] m
         .for_each(|(entity, activate)| {
             let item = ecs.entry_ref(activate.item);
             if let Ok(item) = item {
+                let name = item
+                    .get_component::<Name>()
+                    .map(|name| name.0.clone())
+                    .unwrap_or_default();
+                stats.items_used += 1;
                 if let Ok(healing) = item.get_component::<ProvidesHealing>() {
                     healing_to_apply.push((activate.used_by, healing.amount));
+                    stats.potions_drunk += 1;
+                    log.add(
+                        format!("You drink the {} (+{} HP).", name, healing.amount),
+                        GREEN,
+                    );
+                }
+                if let Ok(explodes) = item.get_component::<Explodes>() {
+                    let center = ecs
+                        .entry_ref(activate.used_by)
+                        .ok()
+                        .and_then(|user| user.get_component::<Point>().ok().copied());
+                    if let Some(center) = center {
+                        blast_walls(map, center, explodes.radius);
+                        <(Entity, &FieldOfView)>::query()
+                            .iter(ecs)
+                            .for_each(|(entity, fov)| {
+                                commands.add_component(*entity, fov.clone_dirty())
+                            });
+                        log.add(
+                            format!("The {} explodes, blasting the walls around you!", name),
+                            ORANGE,
+                        );
+                    }
                 }
                 if item.get_component::<ProvidesDungeonMap>().is_ok() {
                     map.revealed_tiles.iter_mut().for_each(|t| *t = true);
+                    log.add(
+                        format!("You read the {}. The level is revealed.", name),
+                        GREEN,
+                    );
                 }
             }
 
@@ -33,3 +75,16 @@ pub fn use_items(ecs: &mut SubWorld, commands: &mut CommandBuffer, #[resource] m
         }
     }
 }
+
+/// Turns the walls within `radius` of `center` to rubble, except for the edge of the map.
+fn blast_walls(map: &mut Map, center: Point, radius: i32) {
+    for y in center.y - radius..=center.y + radius {
+        for x in center.x - radius..=center.x + radius {
+            let pt = Point::new(x, y);
+            if map.can_dig(pt) && DistanceAlg::Pythagoras.distance2d(center, pt) <= radius as f32 {
+                let idx = map.point2d_to_index(pt);
+                map.tiles[idx] = TileType::Rubble;
+            }
+        }
+    }
+}
@@ -1,4 +1,6 @@
-#[derive(Copy, Clone, Debug, PartialEq)]
+use serde::{Deserialize, Serialize};
+
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub enum TurnState {
     AwaitingInput,
     PlayerTurn,
@@ -6,4 +8,11 @@ pub enum TurnState {
     GameOver,
     Victory,
     NextLevel,
+    PreviousLevel,
+    SaveGame,
+    MainMenu,
 }
+
+/// The number of full turns (the player's and the monsters') played in the run.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct TurnCount(pub u32);
@@ -0,0 +1,104 @@
+use serde::{Deserialize, Serialize};
+
+/// What the player has to achieve to win, as set in the game config. Depths count from 0,
+/// the first level of the dungeon.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum VictoryCondition {
+    /// Reach the artifact lying on level `depth`.
+    RetrieveArtifact { artifact: String, depth: u32 },
+    /// Pick up the artifact lying on level `depth` and carry it back to the first level.
+    ReturnToSurface { artifact: String, depth: u32 },
+    /// Kill the monster built from the `boss` template, which waits on level `depth`.
+    SlayBoss { boss: String, depth: u32 },
+    /// Stay alive for `turns` turns.
+    Survive { turns: u32 },
+}
+
+impl Default for VictoryCondition {
+    fn default() -> Self {
+        VictoryCondition::RetrieveArtifact {
+            artifact: "Amulet of Yala".to_string(),
+            depth: 2,
+        }
+    }
+}
+
+impl VictoryCondition {
+    /// The level where the artifact or the boss is spawned, if there is one.
+    pub fn goal_depth(&self) -> Option<u32> {
+        match self {
+            VictoryCondition::RetrieveArtifact { depth, .. }
+            | VictoryCondition::ReturnToSurface { depth, .. }
+            | VictoryCondition::SlayBoss { depth, .. } => Some(*depth),
+            VictoryCondition::Survive { .. } => None,
+        }
+    }
+
+    /// The goal, as shown to the player during the game.
+    pub fn describe(&self, turns_played: u32) -> String {
+        match self {
+            VictoryCondition::RetrieveArtifact { artifact, depth } => {
+                format!("Find the {} on dungeon level {}.", artifact, depth + 1)
+            }
+            VictoryCondition::ReturnToSurface { artifact, depth } => format!(
+                "Bring the {} from dungeon level {} back to level 1.",
+                artifact,
+                depth + 1
+            ),
+            VictoryCondition::SlayBoss { boss, depth } => {
+                format!("Slay the {} on dungeon level {}.", boss, depth + 1)
+            }
+            VictoryCondition::Survive { turns } => {
+                format!("Survive {} turns ({} so far).", turns, turns_played)
+            }
+        }
+    }
+
+    /// The quest, as told on the main menu.
+    pub fn quest(&self) -> String {
+        let goal = match self {
+            VictoryCondition::RetrieveArtifact { artifact, .. } => format!("Find the {}", artifact),
+            VictoryCondition::ReturnToSurface { artifact, .. } => {
+                format!("Bring the {} back to the surface", artifact)
+            }
+            VictoryCondition::SlayBoss { boss, .. } => format!("Slay the {}", boss),
+            VictoryCondition::Survive { turns } => {
+                format!("Survive {} turns in the dungeon", turns)
+            }
+        };
+        format!("{} and save your home town.", goal)
+    }
+
+    /// What is left undone when the player dies, as shown on the game over screen.
+    pub fn failure(&self) -> String {
+        let undone = match self {
+            VictoryCondition::RetrieveArtifact { artifact, .. }
+            | VictoryCondition::ReturnToSurface { artifact, .. } => {
+                format!("The {} remains unclaimed", artifact)
+            }
+            VictoryCondition::SlayBoss { boss, .. } => format!("The {} still lives", boss),
+            VictoryCondition::Survive { .. } => "The dungeon outlasted you".to_string(),
+        };
+        format!("{}, and your home town is not saved.", undone)
+    }
+
+    /// What the player achieved, as shown on the victory screen.
+    pub fn accomplishment(&self) -> String {
+        match self {
+            VictoryCondition::RetrieveArtifact { artifact, .. } => format!(
+                "You put on the {} and feel its power course through your veins.",
+                artifact
+            ),
+            VictoryCondition::ReturnToSurface { artifact, .. } => format!(
+                "You carried the {} out of the depths and into the light.",
+                artifact
+            ),
+            VictoryCondition::SlayBoss { boss, .. } => {
+                format!("The {} lies dead at your feet.", boss)
+            }
+            VictoryCondition::Survive { turns } => {
+                format!("You outlasted the dungeon for {} turns.", turns)
+            }
+        }
+    }
+}